@@ -0,0 +1,79 @@
+use criterion::{criterion_group, criterion_main, Criterion, Throughput};
+use std::io::{self, Write};
+use std::process::{Command, Stdio};
+use std::{env, fs::File, path::Path};
+use tempfile::NamedTempFile;
+
+const PRG: &str = env!("CARGO_BIN_EXE_catr");
+
+// 256 MiB by default, e.g. CATR_BENCH_BYTES=10000000 for a quick run
+fn num_bytes() -> u64 {
+    env::var("CATR_BENCH_BYTES")
+        .ok()
+        .and_then(|n| n.parse().ok())
+        .unwrap_or(256 * 1024 * 1024)
+}
+
+// --------------------------------------------------
+// Log-like lines until the file holds `n` bytes.
+fn gen_log(n: u64) -> io::Result<NamedTempFile> {
+    let mut file = NamedTempFile::new()?;
+    let mut out = io::BufWriter::new(file.as_file_mut());
+    let mut written = 0;
+    for i in 0.. {
+        if written >= n {
+            break;
+        }
+        let line = format!(
+            "2024-02-{:02} 12:{:02}:{:02} INFO request {i} served in {}ms\n",
+            i % 28 + 1,
+            i % 60,
+            i % 59,
+            i % 997
+        );
+        out.write_all(line.as_bytes())?;
+        written += line.len() as u64;
+    }
+    out.flush()?;
+    drop(out);
+    Ok(file)
+}
+
+// --------------------------------------------------
+// Into /dev/null, or into a pipe the way `catr log | other-tool` would.
+fn catr(file: &Path, args: &[&str], pipe: bool) {
+    let mut cmd = Command::new(PRG);
+    cmd.args(args).arg(file);
+    let status = if pipe {
+        let mut child = cmd.stdout(Stdio::piped()).spawn().expect("failed to run catr");
+        io::copy(&mut child.stdout.take().unwrap(), &mut io::sink()).unwrap();
+        child.wait().unwrap()
+    } else {
+        cmd.stdout(File::create("/dev/null").unwrap()).status().expect("failed to run catr")
+    };
+    assert!(status.success());
+}
+
+// --------------------------------------------------
+fn bench_copy(c: &mut Criterion) {
+    let size = num_bytes();
+    let log = gen_log(size).expect("failed to generate input");
+    let path = log.path();
+
+    let mut group = c.benchmark_group("copy");
+    group.sample_size(10);
+    group.throughput(Throughput::Bytes(size));
+    for (name, pipe) in [("devnull", false), ("pipe", true)] {
+        // 옵션 없음: 커널 복사 경로
+        group.bench_function(format!("{name}/raw"), |b| b.iter(|| catr(path, &[], pipe)));
+        // 줄 단위 경로
+        group.bench_function(format!("{name}/number"), |b| b.iter(|| catr(path, &["-n"], pipe)));
+        group.bench_function(format!("{name}/show-nonprinting"), |b| {
+            b.iter(|| catr(path, &["-v"], pipe))
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_copy);
+criterion_main!(benches);
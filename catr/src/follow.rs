@@ -0,0 +1,155 @@
+/*
+ * --follow: tail -F 처럼 파일 끝에 닿아도 끝내지 않고 새 데이터를 기다림.
+ *
+ * Follow 는 끝(EOF)을 돌려주지 않는 Read 라서, main.rs 의 줄 단위 처리가
+ * 그대로 동작하고 줄 번호도 이어진다.
+ *
+ * 파일이 있는 디렉토리를 notify(리눅스에서는 inotify)로 지켜보다가 이벤트가 오면
+ * 바로 다시 읽고, 이벤트가 없어도 POLL 마다 확인한다. 감시를 못 하는 환경
+ * (inotify 한도 초과, 네트워크 파일시스템 등)에서는 폴링만 함.
+ *
+ * 매번 이름으로 파일을 다시 확인해서
+ *   - 다른 파일로 바뀌었으면(로그 로테이션) 예전 파일을 끝까지 읽은 뒤 새 파일을
+ *     처음부터 읽고
+ *   - 지금 위치보다 작아졌으면(truncate) 처음부터 다시 읽음
+ *   - 잘린 뒤 바로 예전보다 길게 다시 쓰면 크기로는 알 수 없으므로, 마지막으로 읽은
+ *     몇 바이트가 그 자리에 그대로 있는지도 확인함. mtime 은 덧붙일 때도 바뀌고
+ *     빨리 쓰면 같은 값이 나올 수도 있어서 쓰지 않음
+ *
+ * tail -F 처럼 바이트를 그대로 이어서 읽으므로, 예전 파일이 줄바꿈 없이 끝났으면
+ * 그 마지막 줄은 새 파일의 첫 줄과 한 줄로 붙어서 나온다.
+ */
+use notify::{RecommendedWatcher, RecursiveMode, Watcher};
+use std::fs::{self, File, Metadata};
+use std::io::{self, Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Receiver};
+use std::thread;
+use std::time::Duration;
+
+const POLL: Duration = Duration::from_secs(1);
+/// 다시 쓰였는지 확인하려고 기억해 두는 바이트 수
+const TAIL: usize = 64;
+
+pub struct Follow {
+    path: PathBuf,
+    file: File,
+    /// 지금 파일에서 마지막으로 읽은 TAIL 바이트
+    tail: Vec<u8>,
+    /// 마지막으로 읽을 때 파일 끝에 닿았는지
+    at_end: bool,
+    /// 감시를 못 하면 None
+    events: Option<(RecommendedWatcher, Receiver<notify::Result<notify::Event>>)>,
+}
+
+impl Follow {
+    pub fn open(path: &Path) -> io::Result<Follow> {
+        Ok(Follow {
+            path: path.to_path_buf(),
+            file: File::open(path)?,
+            tail: Vec::new(),
+            at_end: false,
+            events: watch(path),
+        })
+    }
+
+    /// 새 데이터가 올 때까지 기다림
+    fn wait(&self) {
+        match &self.events {
+            Some((_, rx)) => {
+                let _ = rx.recv_timeout(POLL);
+                // 한 번 쓸 때 이벤트가 여러 개 오므로 쌓인 것은 비움
+                while rx.try_recv().is_ok() {}
+            }
+            None => thread::sleep(POLL),
+        }
+    }
+
+    /// 파일이 바뀌었거나 잘렸으면 처음부터 다시 읽도록 하고 true
+    fn reopen(&mut self) -> io::Result<bool> {
+        let name = self.path.display();
+        let current = self.file.metadata()?;
+        // 로테이션 중에는 새 파일이 아직 없을 수 있으므로 지금 파일을 계속 봄
+        if let Ok(md) = fs::metadata(&self.path) {
+            if !same_file(&current, &md) {
+                // 로테이션 직전에 덧붙인 줄을 잃지 않도록 예전 파일을 끝까지 읽고 나서 바꿈
+                if current.len() > self.file.stream_position()? {
+                    return Ok(false);
+                }
+                if let Ok(file) = File::open(&self.path) {
+                    eprintln!("catr: {name}: file replaced; following new file");
+                    self.file = file;
+                    self.tail.clear();
+                    return Ok(true);
+                }
+            }
+        }
+        if current.len() < self.file.stream_position()? || self.rewritten()? {
+            eprintln!("catr: {name}: file truncated");
+            self.file.seek(SeekFrom::Start(0))?;
+            self.tail.clear();
+            return Ok(true);
+        }
+        Ok(false)
+    }
+
+    /// 마지막으로 읽은 바이트가 바뀌었으면 잘린 뒤 다시 쓰인 것
+    fn rewritten(&self) -> io::Result<bool> {
+        let mut file = &self.file;
+        let pos = file.stream_position()?;
+        let mut old = vec![0; self.tail.len()];
+        file.seek(SeekFrom::Start(pos - old.len() as u64))?;
+        let same = file.read_exact(&mut old).is_ok() && old == self.tail;
+        file.seek(SeekFrom::Start(pos))?;
+        Ok(!same)
+    }
+}
+
+impl Read for Follow {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+        loop {
+            // 지난번에 끝까지 읽었으면 그 사이 잘리고 다시 쓰였을 수 있으므로 읽기 전에 확인
+            if self.at_end {
+                self.reopen()?;
+            }
+            let n = self.file.read(buf)?;
+            // 일반 파일은 끝에 닿았을 때만 요청보다 적게 읽힘
+            self.at_end = n < buf.len();
+            if n > 0 {
+                self.tail.extend_from_slice(&buf[n.saturating_sub(TAIL)..n]);
+                self.tail.drain(..self.tail.len().saturating_sub(TAIL));
+                return Ok(n);
+            }
+            if !self.reopen()? {
+                self.wait();
+            }
+        }
+    }
+}
+
+/// 파일 자체가 아니라 디렉토리를 봐야 지워지고 새로 생기는 것도 알 수 있음
+fn watch(path: &Path) -> Option<(RecommendedWatcher, Receiver<notify::Result<notify::Event>>)> {
+    let dir = match path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir,
+        _ => Path::new("."),
+    };
+    let (tx, rx) = mpsc::channel();
+    let mut watcher = notify::recommended_watcher(tx).ok()?;
+    watcher.watch(dir, RecursiveMode::NonRecursive).ok()?;
+    Some((watcher, rx))
+}
+
+#[cfg(unix)]
+fn same_file(a: &Metadata, b: &Metadata) -> bool {
+    use std::os::unix::fs::MetadataExt;
+    (a.dev(), a.ino()) == (b.dev(), b.ino())
+}
+
+// 파일 번호를 알 수 없으면 잘리는 것만 알아챔
+#[cfg(not(unix))]
+fn same_file(_a: &Metadata, _b: &Metadata) -> bool {
+    true
+}
@@ -44,10 +44,25 @@ fn get_args() -> Args {
     }
 }
 */
+mod follow;
+mod pretty;
+mod reverse;
+mod select;
+
 use clap::Parser;
-use anyhow::Result;
+use anyhow::{bail, Result};
+use bzip2::read::MultiBzDecoder;
+use flate2::read::MultiGzDecoder;
 use std::fs::File;
-use std::io::{self, BufRead, BufReader};
+use follow::Follow;
+use pretty::{Pager, Pretty};
+use regex::bytes::Regex;
+use reverse::Reverse;
+use select::{LineRange, Selection};
+use std::io::{self, BufRead, BufReader, BufWriter, IsTerminal, Read, StdoutLock, Write};
+use std::path::Path;
+use xz2::read::XzDecoder;
+use zstd::stream::read::Decoder as ZstdDecoder;
 
 #[derive(Parser, Debug)]
 #[command(author, version, about)]
@@ -71,6 +86,116 @@ struct Args {
         long("number-nonblank")
     )]
     number_nonblank_lines: bool,
+
+    /// restart line numbers at 1 for every file
+    #[arg(long("number-per-file"))]
+    number_per_file: bool,
+
+    /// minimum width of line numbers
+    // 포맷 폭은 u16 까지만 가능
+    #[arg(long("number-width"), value_name = "WIDTH", default_value_t = 6)]
+    number_width: u16,
+
+    /// text between a line number and the line
+    #[arg(long("number-separator"), value_name = "SEP", default_value = "\t")]
+    number_separator: String,
+
+    /// print only lines A to B, counted from 1 in each file; A: and :B leave one end open
+    #[arg(long("lines"), value_name = "A:B", value_parser(LineRange::parse))]
+    lines: Option<LineRange>,
+
+    /// print lines starting at the first line matching REGEX;
+    /// with --lines, --from and --to only look at lines in that range
+    #[arg(long("from"), value_name = "REGEX", value_parser(Regex::new))]
+    from: Option<Regex>,
+
+    /// print lines up to the next line matching REGEX
+    #[arg(long("to"), value_name = "REGEX", value_parser(Regex::new))]
+    to: Option<Regex>,
+
+    /// decompress gzip, bzip2, xz and zstd input, recognized by its magic bytes
+    #[arg(short('z'), long("decompress"))]
+    decompress: bool,
+
+    /// print lines in reverse order, last line first (like tac)
+    #[arg(long("reverse"))]
+    reverse: bool,
+
+    /// with --reverse: use SEP instead of newline to split records;
+    /// -n and -b still number newline-separated lines, not records
+    #[arg(
+        long("separator"),
+        value_name = "SEP",
+        requires("reverse"),
+        value_parser = clap::builder::NonEmptyStringValueParser::new()
+    )]
+    separator: Option<String>,
+
+    /// after the end of FILE, keep waiting for data appended to it;
+    /// reopens FILE when it is truncated or replaced; an unfinished last line
+    /// is joined to the first line read after that
+    #[arg(long("follow"), conflicts_with_all(["reverse", "decompress", "pretty"]))]
+    follow: bool,
+
+    /// on a terminal: highlight syntax, show file names and line numbers, and page long output
+    #[arg(
+        long("pretty"),
+        conflicts_with_all(["show_all", "show_nonprinting_ends", "show_ends", "show_nonprinting_tabs", "show_tabs", "show_nonprinting"])
+    )]
+    pretty: bool,
+
+    /// equivalent to -vET
+    #[arg(short('A'), long("show-all"))]
+    show_all: bool,
+
+    /// equivalent to -vE
+    #[arg(short('e'))]
+    show_nonprinting_ends: bool,
+
+    /// display $ at end of each line
+    #[arg(short('E'), long("show-ends"))]
+    show_ends: bool,
+
+    /// suppress repeated empty output lines
+    #[arg(short('s'), long("squeeze-blank"))]
+    squeeze_blank: bool,
+
+    /// equivalent to -vT
+    #[arg(short('t'))]
+    show_nonprinting_tabs: bool,
+
+    /// display TAB characters as ^I
+    #[arg(short('T'), long("show-tabs"))]
+    show_tabs: bool,
+
+    /// use ^ and M- notation, except for LFD and TAB
+    #[arg(short('v'), long("show-nonprinting"))]
+    show_nonprinting: bool,
+}
+
+/// -v, -E, -T 와 그 축약 옵션(-A, -e, -t)을 합친 결과
+#[derive(Debug, Clone, Copy)]
+struct Show {
+    nonprinting: bool,
+    ends: bool,
+    tabs: bool,
+}
+
+impl Show {
+    fn from_args(args: &Args) -> Show {
+        Show {
+            nonprinting: args.show_nonprinting
+                || args.show_all
+                || args.show_nonprinting_ends
+                || args.show_nonprinting_tabs,
+            ends: args.show_ends || args.show_all || args.show_nonprinting_ends,
+            tabs: args.show_tabs || args.show_all || args.show_nonprinting_tabs,
+        }
+    }
+
+    fn is_raw(&self) -> bool {
+        !self.nonprinting && !self.tabs
+    }
 }
 
 /*
@@ -104,41 +229,337 @@ fn fileopen(fname: &str) -> Result<Box<dyn BufRead>> {
     }
 }
 
+/*
+ * --reverse 용. 일반 파일은 바로 거꾸로 읽고, Seek 할 수 없는 입력(stdin, 파이프,
+ * 압축을 푼 스트림)은 fileopen() 으로 열어서 임시 파일에 옮겨 둔 다음 거꾸로 읽음.
+ * 임시 파일은 이름 없이 만들어서 닫히면 저절로 지워진다.
+ */
+fn reverse_open(fname: &str, decompress_input: bool, separator: &[u8]) -> Result<Box<dyn BufRead>> {
+    if fname != "-" && !decompress_input {
+        let file = File::open(fname)?;
+        if file.metadata()?.is_file() {
+            return Ok(Box::new(BufReader::new(Reverse::new(file, separator)?)));
+        }
+    }
+    let mut input = fileopen(fname)?;
+    if decompress_input {
+        input = decompress(input)?;
+    }
+    let mut spool = tempfile::tempfile()?;
+    io::copy(&mut input, &mut spool)?;
+    Ok(Box::new(BufReader::new(Reverse::new(spool, separator)?)))
+}
+
+/*
+ * 파일 이름 대신 맨 앞 몇 바이트(magic number)로 압축 형식을 알아냄.
+ * 파이프는 한 번에 몇 바이트만 줄 수도 있으므로 6 바이트를 다 받거나 끝날 때까지 읽고,
+ * 읽은 앞부분은 chain 으로 다시 앞에 붙여서 압축이 아닌 파일도 그대로 읽을 수 있다.
+ * 여러 스트림이 이어 붙은 파일(cat a.gz b.gz > c.gz)도 gzip -dc 처럼 끝까지 풀어줌.
+ */
+fn decompress(mut reader: Box<dyn BufRead>) -> Result<Box<dyn BufRead>> {
+    let mut magic = Vec::with_capacity(6);
+    reader.by_ref().take(6).read_to_end(&mut magic)?;
+    let reader: Box<dyn BufRead> = Box::new(io::Cursor::new(magic.clone()).chain(reader));
+    let decoded: Box<dyn Read> = if magic.starts_with(&[0x1f, 0x8b]) {
+        Box::new(MultiGzDecoder::new(reader))
+    } else if magic.starts_with(b"BZh") {
+        Box::new(MultiBzDecoder::new(reader))
+    } else if magic.starts_with(&[0xfd, b'7', b'z', b'X', b'Z', 0x00]) {
+        Box::new(XzDecoder::new_multi_decoder(reader))
+    } else if magic.starts_with(&[0x28, 0xb5, 0x2f, 0xfd]) {
+        Box::new(ZstdDecoder::with_buffer(reader)?)
+    } else {
+        return Ok(reader);
+    };
+    Ok(Box::new(BufReader::new(decoded)))
+}
+
+/*
+ * GNU cat 과 같은 표기법:
+ *   제어문자 0x00-0x1f -> ^@ .. ^_ (탭은 -T 일 때만 ^I, 줄바꿈은 그대로)
+ *   0x7f -> ^?
+ *   0x80 이상 -> M- 를 붙이고 0x80 을 뺀 값을 위와 같이 표시
+ */
+fn write_visible(out: &mut impl Write, line: &[u8], show: Show) -> io::Result<()> {
+    let (mut body, newline) = match line.strip_suffix(b"\n") {
+        Some(body) => (body, true),
+        None => (line, false),
+    };
+    // -E 만 있어도 줄 끝의 CR 은 ^M 으로 보임 (GNU cat 과 같음)
+    let mut cr = false;
+    if newline && show.ends && !show.nonprinting {
+        if let Some(rest) = body.strip_suffix(b"\r") {
+            body = rest;
+            cr = true;
+        }
+    }
+    if show.is_raw() {
+        out.write_all(body)?;
+    } else {
+        let mut buf = Vec::with_capacity(body.len());
+        for &c in body {
+            match c {
+                b'\t' if show.tabs => buf.extend_from_slice(b"^I"),
+                b'\t' => buf.push(c),
+                _ if !show.nonprinting => buf.push(c),
+                _ => {
+                    let c = if c >= 0x80 {
+                        buf.extend_from_slice(b"M-");
+                        c - 0x80
+                    } else {
+                        c
+                    };
+                    match c {
+                        0..=0x1f => buf.extend_from_slice(&[b'^', c + 0x40]),
+                        0x7f => buf.extend_from_slice(b"^?"),
+                        _ => buf.push(c),
+                    }
+                }
+            }
+        }
+        out.write_all(&buf)?;
+    }
+    if cr {
+        out.write_all(b"^M")?;
+    }
+    if newline {
+        if show.ends {
+            out.write_all(b"$")?;
+        }
+        out.write_all(b"\n")?;
+    }
+    Ok(())
+}
+
+// 커널 복사를 못 쓸 때 한 번에 옮기는 크기
+const BUF_SIZE: usize = 128 * 1024;
+
+/*
+ * 옵션이 없을 때의 빠른 경로.
+ * io::copy 는 리눅스에서 양쪽이 File, Stdin/StdoutLock, BufReader/BufWriter 같은
+ * 구체 타입이면 copy_file_range -> sendfile -> splice 순서로 커널 안에서 복사하고,
+ * 안 되면 BufWriter 의 버퍼로 읽고 씀. Box<dyn BufRead> 로 감싸면 이 특수화가
+ * 사라지므로 fileopen() 을 거치지 않음.
+ */
+fn copy_raw(fname: &str, out: &mut BufWriter<StdoutLock>) -> Result<(), CatError> {
+    let copied = match fname {
+        "-" => io::copy(&mut io::stdin().lock(), out),
+        _ => io::copy(&mut File::open(fname).map_err(CatError::input)?, out),
+    };
+    // io::copy 는 읽기와 쓰기 중 어느 쪽에서 실패했는지 알려주지 않으므로 종류로 구분
+    copied.map(|_| ()).map_err(|e| match e.kind() {
+        io::ErrorKind::BrokenPipe
+        | io::ErrorKind::StorageFull
+        | io::ErrorKind::QuotaExceeded
+        | io::ErrorKind::FileTooLarge
+        | io::ErrorKind::WriteZero => CatError::Output(e),
+        _ => CatError::input(e),
+    })
+}
+
+/*
+ * 입력 파일에서 난 에러는 그 파일만 건너뛰고, 출력에서 난 에러는 더 쓸 곳이 없으므로 끝냄.
+ * 쓰기는 io::Result 를 돌려주므로 ? 로 넘기면 Output 이 되고, 읽기 쪽만 Input 으로 감쌈.
+ */
+enum CatError {
+    Input(anyhow::Error),
+    Output(io::Error),
+}
+
+impl CatError {
+    fn input(e: io::Error) -> CatError {
+        CatError::Input(e.into())
+    }
+}
+
+impl From<io::Error> for CatError {
+    fn from(e: io::Error) -> CatError {
+        CatError::Output(e)
+    }
+}
+
+/// 출력 에러는 입력 파일 이름 없이 GNU cat 처럼 알림. 파이프가 닫힌 것은 조용히 끝냄
+fn output_error(e: io::Error) -> Result<bool> {
+    match e.kind() {
+        io::ErrorKind::BrokenPipe => Ok(false),
+        _ => bail!("write error: {e}"),
+    }
+}
+
 /*
  * 에러발생 케이스:
  * Permission denied (os error 13)
  * No such file or directory (os error 2)
+ * Is a directory (os error 21)
+ *
+ * cat 처럼 파일 하나가 실패해도 나머지 파일은 계속 출력하고,
+ * 실패한 파일이 있으면 Ok(false) 를 돌려줘서 main 이 1 로 종료하게 함.
  */
-fn run(_args: Args) -> Result<()> {
+fn run(_args: Args) -> Result<bool> {
+    if _args.follow && (_args.files.len() != 1 || _args.files[0] == "-") {
+        bail!("--follow needs exactly one FILE, not stdin");
+    }
     let opt_n: bool = _args.number_lines;
     let opt_b: bool = _args.number_nonblank_lines;
-    for fname in _args.files {
-        match fileopen(&fname) {
-            Err(e) => eprintln!("{fname}: fail to open: {e}"),
-            Ok(buf_reader) => {
-                let mut lnum: i32 = 0;
-                for read in buf_reader.lines() {
-                    let txt = read?;
-                //            ^^^^ Result<std::string::String, std::io::Error> 타입.
-                //            unwrap필요, ?사용
-                    let mut prefix = String::from("");
-                    if opt_n || (opt_b && !txt.is_empty()) {
-                        lnum = lnum + 1;
-                        prefix = format!("{lnum:>6}\t");
+    let opt_s: bool = _args.squeeze_blank;
+    let show = Show::from_args(&_args);
+    let selection = Selection {
+        lines: _args.lines,
+        from: _args.from.clone(),
+        to: _args.to.clone(),
+    };
+    // 파이프로 나갈 때는 --pretty 를 무시해서 cat 과 똑같은 바이트를 출력
+    let pretty = (_args.pretty && io::stdout().is_terminal()).then(Pretty::new);
+    // 옵션이 없으면 줄 단위로 나눌 필요 없이 바이트를 그대로 복사
+    let passthrough = !opt_n
+        && !opt_b
+        && !opt_s
+        && show.is_raw()
+        && !show.ends
+        && !_args.decompress
+        && !_args.reverse
+        && !_args.follow
+        && selection.is_all()
+        && pretty.is_none();
+    // --pretty 는 따로 말하지 않으면 모든 줄에 번호를 붙임
+    let opt_n = opt_n || (pretty.is_some() && !opt_b);
+    // 줄마다 println! 하면 매번 stdout 잠금과 시스템콜이 일어나므로 버퍼링
+    let mut out = BufWriter::with_capacity(BUF_SIZE, io::stdout().lock());
+    // --pretty 는 화면보다 길어지면 페이저로 보냄
+    let mut pager = pretty.as_ref().map(|_| Pager::new(io::stdout()));
+    let separator = _args.separator.as_deref().unwrap_or("\n").as_bytes();
+    let width = usize::from(_args.number_width);
+    let sep = &_args.number_separator;
+    // GNU cat 처럼 파일이 바뀌어도 번호를 이어감. u64 는 넘칠 일이 없음
+    let mut lnum: u64 = 0;
+    // 앞 파일이 \n 없이 끝났으면 다음 파일의 첫 줄은 그 줄의 뒷부분이라 번호를 붙이지 않음
+    let mut at_line_start = true;
+    // -s 도 파일 경계를 넘어서 빈 줄을 합침
+    let mut prev_blank = false;
+    let mut cat = |fname: &str, last: bool| -> Result<(), CatError> {
+        if passthrough {
+            return copy_raw(fname, &mut out);
+        }
+        let mut buf_reader: Box<dyn BufRead> = if _args.follow {
+            Box::new(BufReader::new(Follow::open(Path::new(fname)).map_err(CatError::input)?))
+        } else {
+            match (_args.reverse, _args.decompress) {
+                (true, _) => reverse_open(fname, _args.decompress, separator),
+                (false, true) => fileopen(fname).and_then(decompress),
+                (false, false) => fileopen(fname),
+            }
+            .map_err(CatError::Input)?
+        };
+        let mut w: &mut dyn Write = match &mut pager {
+            Some(pager) => pager,
+            None => &mut out,
+        };
+        if pretty.is_some() {
+            pretty::write_header(&mut w, fname)?;
+        }
+        let mut highlighter = None;
+        if _args.number_per_file {
+            lnum = 0;
+            at_line_start = true;
+            prev_blank = false;
+        }
+        let mut select = selection.start();
+        // 범위가 끝나도 다음 파일의 번호를 이어가려면 끝까지 세어야 함
+        let count_rest = (opt_n || opt_b) && !_args.number_per_file && !last;
+        // lines() 는 UTF-8 만 읽을 수 있고 \r\n 도 지워버리므로 바이트 단위로 읽음
+        let mut line = Vec::new();
+        loop {
+            line.clear();
+            if select.is_done() && !count_rest {
+                break;
+            }
+            if buf_reader.read_until(b'\n', &mut line).map_err(CatError::input)? == 0 {
+                break;
+            }
+            let blank = at_line_start && line == b"\n";
+            let numbered = at_line_start && (opt_n || (opt_b && !blank));
+            at_line_start = line.ends_with(b"\n");
+            if !select.line(&line) {
+                // -n 은 원래 줄 번호를 보여주므로 고르지 않은 줄도 셈
+                if numbered {
+                    lnum += 1;
+                }
+                line.clear();
+                continue;
+            }
+            if opt_s && blank && prev_blank {
+                continue;
+            }
+            prev_blank = blank;
+            if numbered {
+                lnum += 1;
+            }
+            match &pretty {
+                None => {
+                    if numbered {
+                        write!(w, "{lnum:>width$}{sep}")?;
                     }
-                    println!("{}{}", prefix, txt);
+                    write_visible(&mut w, &line, show)?;
                 }
+                Some(pretty) => {
+                    pretty::write_gutter(&mut w, numbered.then_some(lnum), width, sep)?;
+                    let hl = highlighter.get_or_insert_with(|| pretty.highlighter(fname, &line));
+                    pretty.write_line(&mut w, hl, &line)?;
+                }
+            }
+            // 다음 줄은 언제 올지 모르므로 바로 내보냄
+            if _args.follow {
+                w.flush()?;
+            }
+        }
+        // --pretty 는 파일마다 헤더가 있으므로 끝나지 않은 줄도 여기서 끝냄
+        if pretty.is_some() && !at_line_start {
+            writeln!(w)?;
+            at_line_start = true;
+        }
+        Ok(())
+    };
+
+    let mut ok = true;
+    for (i, fname) in _args.files.iter().enumerate() {
+        match cat(fname, i + 1 == _args.files.len()) {
+            Ok(()) => {}
+            Err(CatError::Input(e)) => {
+                eprintln!("catr: {fname}: {e}");
+                ok = false;
+            }
+            Err(CatError::Output(e)) => {
+                if let Some(pager) = pager.take() {
+                    // less 에서 q 로 먼저 끝내면 BrokenPipe 가 나는데 정상 종료임
+                    let _ = pager.finish();
+                    if e.kind() == io::ErrorKind::BrokenPipe {
+                        return Ok(ok);
+                    }
+                }
+                // 출력 쪽이 닫혔으면 (catr ... | head) 더 쓸 곳이 없으므로 조용히 중단
+                return output_error(e);
             }
         }
     }
-    Ok(())
+    let finished = match pager {
+        Some(pager) => pager.finish(),
+        None => out.flush(),
+    };
+    match finished {
+        Ok(()) => Ok(ok),
+        Err(e) => output_error(e),
+    }
 }
 
 fn main() {
     //let args = get_args();
     //let args = Args::parse();
-    if let Err(e) = run(Args::parse()) {
-        eprintln!("{e}");
-        std::process::exit(1);
+    match run(Args::parse()) {
+        Ok(true) => {}
+        Ok(false) => std::process::exit(1),
+        Err(e) => {
+            eprintln!("catr: {e}");
+            std::process::exit(1);
+        }
     }
 }
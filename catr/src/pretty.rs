@@ -0,0 +1,260 @@
+/*
+ * --pretty: bat 처럼 파일 이름 헤더, 문법 강조, 줄 번호를 붙여서 보여줌.
+ * 터미널에 출력할 때만 쓰이고, 파이프로 나갈 때는 main.rs 가 평소처럼 출력한다.
+ *
+ * 문법 정의와 테마는 syntect 에 내장된 것(Sublime Text 형식)을 사용
+ * https://docs.rs/syntect/latest/syntect/
+ */
+use std::env;
+use std::io::{self, Write};
+use std::path::Path;
+use std::process::{Child, ChildStdin, Command, Stdio};
+use syntect::easy::HighlightLines;
+use syntect::highlighting::{Theme, ThemeSet};
+use syntect::parsing::SyntaxSet;
+use syntect::util::as_24_bit_terminal_escaped;
+use terminal_size::{terminal_size, Height, Width};
+
+const THEME: &str = "base16-ocean.dark";
+const DIM: &str = "\x1b[2m";
+const BOLD: &str = "\x1b[1m";
+const RESET: &str = "\x1b[0m";
+
+pub struct Pretty {
+    syntaxes: SyntaxSet,
+    theme: Theme,
+}
+
+impl Pretty {
+    pub fn new() -> Pretty {
+        let mut themes = ThemeSet::load_defaults();
+        Pretty {
+            // 줄 끝의 \n 까지 포함해서 강조하는 문법 정의
+            syntaxes: SyntaxSet::load_defaults_newlines(),
+            theme: themes.themes.remove(THEME).expect("built-in theme"),
+        }
+    }
+
+    /// 확장자로 언어를 찾고, 없으면 첫 줄(#!/bin/sh 같은)로 찾음
+    pub fn highlighter(&self, fname: &str, first_line: &[u8]) -> HighlightLines<'_> {
+        let syntax = self
+            .syntaxes
+            .find_syntax_for_file(Path::new(fname))
+            .ok()
+            .flatten()
+            .or_else(|| self.syntaxes.find_syntax_by_first_line(&String::from_utf8_lossy(first_line)))
+            .unwrap_or_else(|| self.syntaxes.find_syntax_plain_text());
+        HighlightLines::new(syntax, &self.theme)
+    }
+
+    pub fn write_line(&self, out: &mut impl Write, hl: &mut HighlightLines, line: &[u8]) -> io::Result<()> {
+        // UTF-8 이 아닌 바이트는 U+FFFD 로 보임
+        let line = String::from_utf8_lossy(line);
+        let ranges = hl.highlight_line(&line, &self.syntaxes).map_err(io::Error::other)?;
+        let escaped = as_24_bit_terminal_escaped(&ranges, false);
+        // 다음 줄의 줄 번호에 색이 번지지 않도록 줄바꿈 전에 색을 되돌림
+        match escaped.strip_suffix('\n') {
+            Some(body) => writeln!(out, "{body}{RESET}"),
+            None => write!(out, "{escaped}{RESET}"),
+        }
+    }
+}
+
+pub fn write_header(out: &mut impl Write, fname: &str) -> io::Result<()> {
+    let rule = "─".repeat(screen().0);
+    writeln!(out, "{DIM}{rule}{RESET}")?;
+    writeln!(out, "{BOLD}{fname}{RESET}")?;
+    writeln!(out, "{DIM}{rule}{RESET}")
+}
+
+/// 번호가 없는 줄(-b 의 빈 줄)도 본문이 같은 열에서 시작하도록 빈칸을 채움
+pub fn write_gutter(out: &mut impl Write, number: Option<u64>, width: usize, sep: &str) -> io::Result<()> {
+    match number {
+        Some(n) => write!(out, "{DIM}{n:>width$}{sep}{RESET}"),
+        None => write!(out, "{DIM}{:width$}{sep}{RESET}", ""),
+    }
+}
+
+/*
+ * 화면보다 길어지면 그때 $PAGER 를 띄우고 나머지는 페이저로 흘려보냄.
+ * 한 화면을 채울 때까지만 모아 두므로 출력이 아무리 길어도 메모리는 한 화면 분량만 씀.
+ * git 처럼 LESS 가 없으면 FRX 를 줘서 색이 보이게(R) 함.
+ */
+pub struct Pager<W: Write> {
+    out: W,
+    height: usize,
+    /// 페이저를 쓰지 않으면 None
+    command: Option<String>,
+    /// 화면을 채우기 전까지 모아 둔 출력
+    held: Vec<u8>,
+    lines: usize,
+    child: Option<(Child, ChildStdin)>,
+}
+
+impl<W: Write> Pager<W> {
+    pub fn new(out: W) -> Pager<W> {
+        let command = env::var("PAGER").unwrap_or_else(|_| "less".to_string());
+        let command = (!command.is_empty() && command != "cat").then_some(command);
+        Pager::with_command(out, screen().1, command)
+    }
+
+    fn with_command(out: W, height: usize, command: Option<String>) -> Pager<W> {
+        Pager {
+            out,
+            height,
+            command,
+            held: Vec::new(),
+            lines: 0,
+            child: None,
+        }
+    }
+
+    fn spawn(&self) -> Option<(Child, ChildStdin)> {
+        let mut cmd = Command::new("sh");
+        cmd.args(["-c", self.command.as_deref()?]).stdin(Stdio::piped());
+        if env::var_os("LESS").is_none() {
+            cmd.env("LESS", "FRX");
+        }
+        let mut child = cmd.spawn().ok()?;
+        let stdin = child.stdin.take()?;
+        Some((child, stdin))
+    }
+
+    /// 모아 둔 것을 내보내고 페이저가 끝날 때까지 기다림
+    pub fn finish(mut self) -> io::Result<()> {
+        match self.child.take() {
+            Some((mut child, stdin)) => {
+                drop(stdin);
+                child.wait()?;
+                Ok(())
+            }
+            None => {
+                self.out.write_all(&self.held)?;
+                self.out.flush()
+            }
+        }
+    }
+}
+
+impl<W: Write> Write for Pager<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if let Some((_, stdin)) = &mut self.child {
+            return stdin.write(buf);
+        }
+        if self.command.is_none() {
+            return self.out.write(buf);
+        }
+        self.held.extend_from_slice(buf);
+        self.lines += buf.iter().filter(|&&b| b == b'\n').count();
+        if self.lines >= self.height {
+            let held = std::mem::take(&mut self.held);
+            match self.spawn() {
+                Some(child) => self.child.insert(child).1.write_all(&held)?,
+                // 페이저를 못 띄우면 그냥 터미널에 출력
+                None => {
+                    self.command = None;
+                    self.out.write_all(&held)?;
+                }
+            }
+        }
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        match &mut self.child {
+            Some((_, stdin)) => stdin.flush(),
+            None => self.out.flush(),
+        }
+    }
+}
+
+/// (너비, 높이), 터미널이 아니면 80x24
+fn screen() -> (usize, usize) {
+    match terminal_size() {
+        Some((Width(w), Height(h))) => (w.into(), h.into()),
+        None => (80, 24),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn header() {
+        let mut out = Vec::new();
+        write_header(&mut out, "src/main.rs").unwrap();
+        let out = String::from_utf8(out).unwrap();
+        let lines: Vec<&str> = out.lines().collect();
+        assert_eq!(lines.len(), 3);
+        assert_eq!(lines[1], format!("{BOLD}src/main.rs{RESET}"));
+        for rule in [lines[0], lines[2]] {
+            assert!(rule.starts_with(DIM) && rule.ends_with(RESET) && rule.contains('─'));
+        }
+    }
+
+    #[test]
+    fn highlight_line() {
+        let pretty = Pretty::new();
+        let mut hl = pretty.highlighter("hello.rs", b"fn main() {\n");
+        let mut out = Vec::new();
+        pretty.write_line(&mut out, &mut hl, b"fn main() {\n").unwrap();
+        let out = String::from_utf8(out).unwrap();
+        // 24 비트 색으로 칠하고 줄바꿈 전에 색을 되돌림
+        assert!(out.contains("\x1b[38;2;"));
+        assert!(out.ends_with(&format!("{RESET}\n")));
+        let plain = regex::Regex::new("\x1b\\[[0-9;]*m").unwrap().replace_all(&out, "");
+        assert_eq!(plain, "fn main() {\n");
+
+        // 줄바꿈이 없는 마지막 줄에는 줄바꿈을 붙이지 않음
+        let mut out = Vec::new();
+        pretty.write_line(&mut out, &mut hl, b"}").unwrap();
+        assert!(String::from_utf8(out).unwrap().ends_with(&format!("}}{RESET}")));
+    }
+
+    #[test]
+    fn gutter() {
+        let mut out = Vec::new();
+        write_gutter(&mut out, Some(7), 4, "\t").unwrap();
+        write_gutter(&mut out, None, 4, "\t").unwrap();
+        assert_eq!(String::from_utf8(out).unwrap(), format!("{DIM}   7\t{RESET}{DIM}    \t{RESET}"));
+    }
+
+    #[test]
+    fn page_short_output() {
+        // 한 화면 안에 끝나면 페이저를 띄우지 않고 그대로 출력
+        let dir = tempfile::tempdir().unwrap();
+        let paged = dir.path().join("paged");
+        let mut out = Vec::new();
+        let mut pager = Pager::with_command(&mut out, 3, Some(format!("cat > {}", paged.display())));
+        pager.write_all(b"one\ntwo\n").unwrap();
+        pager.finish().unwrap();
+        assert_eq!(out, b"one\ntwo\n");
+        assert!(!paged.exists());
+    }
+
+    #[test]
+    fn page_long_output() {
+        // 화면을 넘으면 그때 페이저를 띄우고 모아 둔 것부터 페이저로 보냄
+        let dir = tempfile::tempdir().unwrap();
+        let paged = dir.path().join("paged");
+        let mut out = Vec::new();
+        let mut pager = Pager::with_command(&mut out, 3, Some(format!("cat > {}", paged.display())));
+        for line in ["one\n", "two\n", "three\n", "four\n"] {
+            pager.write_all(line.as_bytes()).unwrap();
+        }
+        pager.finish().unwrap();
+        assert!(out.is_empty());
+        assert_eq!(std::fs::read_to_string(&paged).unwrap(), "one\ntwo\nthree\nfour\n");
+    }
+
+    #[test]
+    fn page_without_pager() {
+        // $PAGER 가 없으면 모으지 않고 바로 출력
+        let mut out = Vec::new();
+        let mut pager = Pager::with_command(&mut out, 3, None);
+        pager.write_all(b"one\n").unwrap();
+        assert_eq!(pager.out.as_slice(), b"one\n");
+        pager.finish().unwrap();
+    }
+}
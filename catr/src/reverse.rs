@@ -0,0 +1,142 @@
+/*
+ * --reverse: tac 처럼 마지막 줄부터 출력.
+ *
+ * 파일 전체를 메모리에 올리지 않고 끝에서부터 BLOCK 크기씩 거꾸로 읽는다.
+ * 줄(레코드)은 구분자까지 포함하고, 그 뒤부터 다음 구분자까지가 한 레코드.
+ * 마지막 레코드가 구분자로 끝나지 않으면 GNU tac 처럼 그대로 붙여서 출력함
+ *   printf 'a\nb' | tac  ->  "ba\n"
+ *
+ * 읽은 블록은 뒤집어서 rbuf 뒤에 붙인다. 앞에 붙이면 아주 긴 줄에서 매번 전체를
+ * 복사하게 되지만, 뒤집어 두면 붙이기도 구분자 찾기도 앞에서부터 한 번씩만 하면 됨.
+ *
+ * 여러 바이트 구분자가 겹쳐서 나올 때(구분자 aa, 내용 aaa)는 GNU tac 과 같게 끝에서부터
+ * 겹치지 않는 것을 찾는데, tac 이 8 KiB 조각 단위로 읽으면서 생기는 차이까지 따라한다.
+ * tac 은 조각의 맨 앞에서 구분자를 찾으면 그 앞 조각은 경계 바로 앞부터 다시 찾으므로,
+ * 방금 찾은 구분자와 겹치는 구분자도 찾는다 (skip() 참고).
+ *
+ * Seek 가 필요하므로 stdin 이나 압축 해제한 스트림은 임시 파일에 먼저 복사해서 읽음.
+ */
+use std::io::{self, Read, Seek, SeekFrom};
+
+const BLOCK: usize = 64 * 1024;
+/// GNU tac 이 한 번에 읽는 크기. 구분자가 아주 길면 두 배씩 늘림
+const TAC_CHUNK: u64 = 8 * 1024;
+
+pub struct Reverse<R> {
+    inner: R,
+    /// 뒤집은 구분자
+    rsep: Vec<u8>,
+    /// tac 이 읽는 조각 크기
+    chunk: u64,
+    /// 아직 읽지 않은 부분의 끝. 0 이면 더 읽을 것이 없음
+    pos: u64,
+    /// 읽었지만 출력하지 않은 부분을 뒤집은 것 (rbuf[head] 가 파일에서 가장 뒤의 바이트)
+    rbuf: Vec<u8>,
+    head: usize,
+    /// rbuf[head..] 에서 이 위치 전까지는 구분자가 없음
+    scanned: usize,
+    /// 지금 레코드가 구분자로 끝나는지. 파일 끝을 읽기 전에는 모름
+    trailing: Option<bool>,
+    /// 지금 출력 중인 레코드
+    record: Vec<u8>,
+    record_pos: usize,
+}
+
+impl<R: Read + Seek> Reverse<R> {
+    pub fn new(mut inner: R, separator: &[u8]) -> io::Result<Reverse<R>> {
+        let pos = inner.seek(SeekFrom::End(0))?;
+        let mut chunk = TAC_CHUNK;
+        while separator.len() as u64 + 1 >= chunk / 2 {
+            chunk *= 2;
+        }
+        Ok(Reverse {
+            inner,
+            rsep: separator.iter().rev().copied().collect(),
+            chunk,
+            pos,
+            rbuf: Vec::new(),
+            head: 0,
+            scanned: 0,
+            trailing: None,
+            record: Vec::new(),
+            record_pos: 0,
+        })
+    }
+
+    /// 뒤에서부터 다음 레코드, 다 읽었으면 None
+    fn next_record(&mut self) -> io::Result<Option<Vec<u8>>> {
+        let sep = self.rsep.len();
+        loop {
+            let data = &self.rbuf[self.head..];
+            if self.pos == 0 && data.is_empty() {
+                return Ok(None);
+            }
+            if self.trailing.is_none() && (data.len() >= sep || self.pos == 0) {
+                self.trailing = Some(data.starts_with(&self.rsep));
+            }
+            if let Some(trailing) = self.trailing {
+                // 지금 레코드의 끝에 있는 구분자는 건너뛰고 그 앞의 구분자를 찾음
+                let end = self.pos + data.len() as u64;
+                let from = self.scanned.max(if trailing { self.skip(end) } else { 0 });
+                let found = data.get(from..).and_then(|d| d.windows(sep).position(|w| w == self.rsep));
+                if let Some(i) = found {
+                    let len = from + i;
+                    let record = data[..len].iter().rev().copied().collect();
+                    self.head += len;
+                    self.scanned = 0;
+                    self.trailing = Some(true);
+                    return Ok(Some(record));
+                }
+                // 끝의 sep - 1 바이트는 다음 블록과 이어서 구분자가 될 수 있음
+                self.scanned = from.max((data.len() + 1).saturating_sub(sep));
+            }
+            if self.pos == 0 {
+                let record = data.iter().rev().copied().collect();
+                self.head = self.rbuf.len();
+                return Ok(Some(record));
+            }
+            let n = BLOCK.min(self.pos as usize);
+            self.pos -= n as u64;
+            self.inner.seek(SeekFrom::Start(self.pos))?;
+            let mut block = vec![0; n];
+            self.inner.read_exact(&mut block)?;
+            self.rbuf.drain(..self.head);
+            self.head = 0;
+            self.rbuf.extend(block.iter().rev());
+        }
+    }
+}
+
+impl<R> Reverse<R> {
+    /// end 에서 끝나는 구분자 앞에서 다음 구분자를 찾기 시작할 곳 (rbuf[head..] 에서의 위치).
+    /// 보통은 구분자 바로 앞이지만, 구분자가 tac 의 조각 맨 앞 sep - 1 바이트 안에서 시작하면
+    /// tac 처럼 조각 경계 바로 앞부터 찾아서 겹치는 구분자도 찾음
+    fn skip(&self, end: u64) -> usize {
+        let sep = self.rsep.len() as u64;
+        let start = end - sep;
+        let chunk_start = start - start % self.chunk;
+        if chunk_start > 0 && start - chunk_start < sep - 1 {
+            (start - chunk_start + 1) as usize
+        } else {
+            sep as usize
+        }
+    }
+}
+
+impl<R: Read + Seek> Read for Reverse<R> {
+    fn read(&mut self, out: &mut [u8]) -> io::Result<usize> {
+        if self.record_pos == self.record.len() {
+            match self.next_record()? {
+                Some(record) => {
+                    self.record = record;
+                    self.record_pos = 0;
+                }
+                None => return Ok(0),
+            }
+        }
+        let n = out.len().min(self.record.len() - self.record_pos);
+        out[..n].copy_from_slice(&self.record[self.record_pos..self.record_pos + n]);
+        self.record_pos += n;
+        Ok(n)
+    }
+}
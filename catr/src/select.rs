@@ -0,0 +1,126 @@
+/*
+ * --lines A:B 와 --from/--to 로 파일마다 일부 줄만 출력.
+ *
+ * 줄 번호는 파일의 원래 번호이고, 범위가 끝나면 Select::is_done() 이 true 가 되어
+ * 나머지는 읽지 않아도 됨. 정규식은 sed 의 /X/,/Y/ 처럼 처음 맞는 범위 하나만 고르고,
+ * --to 는 --from 이 맞은 다음 줄부터 찾는다. --lines 가 있으면 정규식은 그 범위 안의
+ * 줄에서만 찾으므로 --from 이 없으면 --lines 의 첫 줄부터 시작함.
+ */
+use regex::bytes::Regex;
+
+/// 1 부터 세는 줄 번호, 양 끝 포함
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LineRange {
+    pub start: u64,
+    pub end: Option<u64>,
+}
+
+impl LineRange {
+    /// `A:B`, `A:`, `:B` 또는 한 줄만 `A`
+    pub fn parse(s: &str) -> Result<LineRange, String> {
+        let number = |n: &str, default: u64| -> Result<u64, String> {
+            match n {
+                "" => Ok(default),
+                _ => match n.parse() {
+                    Ok(0) | Err(_) => Err(format!("invalid line number \"{n}\"")),
+                    Ok(n) => Ok(n),
+                },
+            }
+        };
+        let range = match s.split_once(':') {
+            Some((start, end)) => LineRange {
+                start: number(start, 1)?,
+                end: (!end.is_empty()).then(|| number(end, 0)).transpose()?,
+            },
+            None if s.is_empty() => return Err("empty range".to_string()),
+            None => {
+                let n = number(s, 0)?;
+                LineRange { start: n, end: Some(n) }
+            }
+        };
+        match range.end {
+            Some(end) if end < range.start => Err(format!("range \"{s}\" ends before it starts")),
+            _ => Ok(range),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Selection {
+    pub lines: Option<LineRange>,
+    pub from: Option<Regex>,
+    pub to: Option<Regex>,
+}
+
+impl Selection {
+    /// 모든 줄을 고르는지
+    pub fn is_all(&self) -> bool {
+        self.lines.is_none() && self.from.is_none() && self.to.is_none()
+    }
+
+    /// 파일 하나를 처음부터 읽을 때마다 새로 만듦
+    pub fn start(&self) -> Select<'_> {
+        Select {
+            selection: self,
+            line_no: 0,
+            inside: false,
+            done: false,
+        }
+    }
+}
+
+pub struct Select<'a> {
+    selection: &'a Selection,
+    line_no: u64,
+    inside: bool,
+    done: bool,
+}
+
+impl Select<'_> {
+    /// 다음 줄을 출력할지
+    pub fn line(&mut self, line: &[u8]) -> bool {
+        self.line_no += 1;
+        if self.done {
+            return false;
+        }
+        let mut selected = true;
+        if let Some(range) = self.selection.lines {
+            selected = self.line_no >= range.start && range.end.is_none_or(|end| self.line_no <= end);
+            if range.end.is_some_and(|end| self.line_no >= end) {
+                self.done = true;
+            }
+        }
+        if selected && (self.selection.from.is_some() || self.selection.to.is_some()) {
+            selected = self.pattern(line.strip_suffix(b"\n").unwrap_or(line));
+        }
+        selected
+    }
+
+    fn pattern(&mut self, text: &[u8]) -> bool {
+        let Selection { from, to, .. } = self.selection;
+        let is_end = |text| to.as_ref().is_some_and(|to| to.is_match(text));
+        if !self.inside {
+            match from {
+                Some(from) if !from.is_match(text) => return false,
+                Some(_) => self.inside = true,
+                // --from 이 없으면 첫 줄부터, 첫 줄도 --to 의 대상
+                None => {
+                    self.inside = true;
+                    if is_end(text) {
+                        self.done = true;
+                    }
+                }
+            }
+            return true;
+        }
+        if is_end(text) {
+            self.done = true;
+        }
+        true
+    }
+
+    /// 범위가 끝나서 더 고를 줄이 없는지
+    pub fn is_done(&self) -> bool {
+        self.done
+    }
+}
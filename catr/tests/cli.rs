@@ -10,6 +10,9 @@ const EMPTY: &str = "tests/inputs/empty.txt";
 const FOX: &str = "tests/inputs/fox.txt";
 const SPIDERS: &str = "tests/inputs/spiders.txt";
 const BUSTLE: &str = "tests/inputs/the-bustle.txt";
+const SPECIAL: &str = "tests/inputs/special.txt";
+const BINARY: &str = "tests/inputs/binary.bin";
+const NO_NEWLINE: &str = "tests/inputs/no-newline.txt";
 
 // --------------------------------------------------
 #[test]
@@ -42,15 +45,102 @@ fn gen_bad_file() -> String {
 #[test]
 fn skips_bad_file() -> Result<()> {
     let bad = gen_bad_file();
-    let expected = format!("{bad}: .* [(]os error 2[)]");
+    let expected = format!("^catr: {bad}: .* [(]os error 2[)]");
     Command::cargo_bin(PRG)?
         .arg(&bad)
         .assert()
-        .success()
+        .failure()
         .stderr(predicate::str::is_match(expected)?);
     Ok(())
 }
 
+// --------------------------------------------------
+#[test]
+fn continues_after_bad_file() -> Result<()> {
+    let bad = gen_bad_file();
+    let fox = fs::read_to_string(FOX)?;
+    for opt in [None, Some("-n")] {
+        let expected = match opt {
+            None => format!("{fox}{fox}"),
+            Some(_) => format!("     1\t{fox}     2\t{fox}"),
+        };
+        Command::cargo_bin(PRG)?
+            .args(opt)
+            .args([FOX, &bad, FOX])
+            .assert()
+            .failure()
+            .stdout(expected)
+            .stderr(predicate::str::is_match(format!("^catr: {bad}: "))?);
+    }
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn write_error() -> Result<()> {
+    // 출력이 실패하면 입력 파일 이름 없이 한 번만 알리고 끝냄
+    let dir = tempfile::tempdir()?;
+    let big = dir.path().join("big.txt");
+    fs::write(&big, "All work and no play makes Jack a dull boy.\n".repeat(100_000))?;
+    for opt in [None, Some("-n")] {
+        let output = std::process::Command::new(assert_cmd::cargo::cargo_bin(PRG))
+            .args(opt)
+            .args([&big, &big])
+            .stdout(fs::File::create("/dev/full")?)
+            .output()?;
+        assert!(!output.status.success());
+        assert_eq!(
+            String::from_utf8(output.stderr)?,
+            "catr: write error: No space left on device (os error 28)\n"
+        );
+    }
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn dir_input() -> Result<()> {
+    let dir = tempfile::tempdir()?;
+    let name = dir.path().display().to_string();
+    for opt in [None, Some("-n")] {
+        Command::cargo_bin(PRG)?
+            .args(opt)
+            .args([&name, FOX])
+            .assert()
+            .failure()
+            .stdout(predicate::str::contains("The quick brown fox"))
+            .stderr(format!("catr: {name}: Is a directory (os error 21)\n"));
+    }
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+#[cfg(unix)]
+fn permission_denied() -> Result<()> {
+    use std::os::unix::fs::PermissionsExt;
+
+    let dir = tempfile::tempdir()?;
+    let file = dir.path().join("cant-touch-this");
+    fs::write(&file, "secret\n")?;
+    fs::set_permissions(&file, fs::Permissions::from_mode(0o000))?;
+    // root 는 권한과 상관없이 읽을 수 있으므로 확인할 수 없음
+    if fs::File::open(&file).is_ok() {
+        eprintln!("skipping permission_denied: file is still readable");
+        return Ok(());
+    }
+    let name = file.display().to_string();
+    Command::cargo_bin(PRG)?
+        .args([&name, FOX])
+        .assert()
+        .failure()
+        .stdout(fs::read_to_string(FOX)?)
+        .stderr(predicate::str::is_match(format!(
+            "^catr: {name}: Permission denied [(]os error 13[)]"
+        ))?);
+    Ok(())
+}
+
 // --------------------------------------------------
 fn run(args: &[&str], expected_file: &str) -> Result<()> {
     let expected = fs::read_to_string(expected_file)?;
@@ -201,3 +291,491 @@ fn all_n() -> Result<()> {
 fn all_b() -> Result<()> {
     run(&[FOX, SPIDERS, BUSTLE, "-b"], "tests/expected/all.b.out")
 }
+
+// --------------------------------------------------
+#[test]
+fn special_show_all() -> Result<()> {
+    run(&["-A", SPECIAL], "tests/expected/special.txt.A.out")?;
+    run(&["--show-all", SPECIAL], "tests/expected/special.txt.A.out")?;
+    run(&["-vET", SPECIAL], "tests/expected/special.txt.A.out")
+}
+
+// --------------------------------------------------
+#[test]
+fn special_show_ends() -> Result<()> {
+    run(&["-E", SPECIAL], "tests/expected/special.txt.E.out")
+}
+
+// --------------------------------------------------
+#[test]
+fn special_show_tabs() -> Result<()> {
+    run(&["--show-tabs", SPECIAL], "tests/expected/special.txt.T.out")
+}
+
+// --------------------------------------------------
+#[test]
+fn special_show_nonprinting() -> Result<()> {
+    run(&["-v", SPECIAL], "tests/expected/special.txt.v.out")
+}
+
+// --------------------------------------------------
+#[test]
+fn special_e_t() -> Result<()> {
+    run(&["-e", SPECIAL], "tests/expected/special.txt.e.out")?;
+    run(&["-t", SPECIAL], "tests/expected/special.txt.t.out")
+}
+
+// --------------------------------------------------
+#[test]
+fn special_squeeze_blank() -> Result<()> {
+    run(&["-s", SPECIAL], "tests/expected/special.txt.s.out")?;
+    run(&["-sn", SPECIAL], "tests/expected/special.txt.sn.out")
+}
+
+// --------------------------------------------------
+#[test]
+fn squeeze_blank_across_files() -> Result<()> {
+    // 앞 파일 끝의 빈 줄과 다음 파일 처음의 빈 줄도 하나로 합침
+    let files = ["tests/inputs/blank-end.txt", "tests/inputs/blank-start.txt"];
+    run(&["-s", files[0], files[1]], "tests/expected/blank-end.blank-start.s.out")?;
+    run(&["-sn", files[0], files[1]], "tests/expected/blank-end.blank-start.sn.out")
+}
+
+// --------------------------------------------------
+#[test]
+fn special_show_all_b() -> Result<()> {
+    run(&["-A", "-b", SPECIAL], "tests/expected/special.txt.Ab.out")
+}
+
+// --------------------------------------------------
+fn run_bytes(args: &[&str], expected_file: &str) -> Result<()> {
+    let expected = fs::read(expected_file)?;
+    let output = Command::cargo_bin(PRG)?.args(args).output().unwrap();
+    assert!(output.status.success());
+    assert!(output.stdout == expected, "output differs from {expected_file}");
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn binary() -> Result<()> {
+    run_bytes(&[BINARY], BINARY)
+}
+
+// --------------------------------------------------
+#[test]
+fn binary_n() -> Result<()> {
+    run_bytes(&["-n", BINARY], "tests/expected/binary.bin.n.out")
+}
+
+// --------------------------------------------------
+#[test]
+fn binary_b() -> Result<()> {
+    run_bytes(&["-b", BINARY], "tests/expected/binary.bin.b.out")
+}
+
+// --------------------------------------------------
+#[test]
+fn binary_stdin() -> Result<()> {
+    let input = fs::read(BINARY)?;
+    let output = Command::cargo_bin(PRG)?
+        .write_stdin(input.clone())
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    assert!(output.stdout == input);
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn crlf_no_final_newline() -> Result<()> {
+    let output = Command::cargo_bin(PRG)?
+        .write_stdin("one\r\ntwo\r\nthree")
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    assert_eq!(output.stdout, b"one\r\ntwo\r\nthree");
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn all_n_per_file() -> Result<()> {
+    run(
+        &[FOX, SPIDERS, BUSTLE, "-n", "--number-per-file"],
+        "tests/expected/all.n.per-file.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn all_b_per_file() -> Result<()> {
+    run(
+        &[FOX, SPIDERS, BUSTLE, "-b", "--number-per-file"],
+        "tests/expected/all.b.per-file.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn n_across_unterminated_file() -> Result<()> {
+    // 첫 파일이 \n 없이 끝나면 다음 파일의 첫 줄은 같은 줄로 이어지므로 번호가 없음
+    run(
+        &["-n", NO_NEWLINE, FOX, SPIDERS],
+        "tests/expected/no-newline.fox.spiders.n.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn b_across_unterminated_file() -> Result<()> {
+    run(
+        &["-b", NO_NEWLINE, FOX, SPIDERS],
+        "tests/expected/no-newline.fox.spiders.b.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn number_format() -> Result<()> {
+    run(
+        &["-n", "--number-width", "1", "--number-separator", ": ", FOX, SPIDERS],
+        "tests/expected/fox.spiders.n.w1.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn dies_bad_number_width() -> Result<()> {
+    for width in ["wide", "70000"] {
+        Command::cargo_bin(PRG)?
+            .args(["-n", "--number-width", width, FOX])
+            .assert()
+            .failure()
+            .code(2)
+            .stderr(predicate::str::contains("--number-width"));
+    }
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn decompress() -> Result<()> {
+    for ext in ["gz", "bz2", "xz", "zst"] {
+        let file = format!("{BUSTLE}.{ext}");
+        run(&["-z", &file], "tests/expected/the-bustle.txt.out")?;
+        run(&["--decompress", "-b", &file], "tests/expected/the-bustle.txt.b.out")?;
+    }
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn decompress_mixed() -> Result<()> {
+    // 두 gzip 스트림을 이어 붙인 파일 + 압축되지 않은 파일
+    run(
+        &["-zn", "tests/inputs/fox-spiders.txt.gz", BUSTLE],
+        "tests/expected/all.n.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn decompress_stdin() -> Result<()> {
+    let input = fs::read(format!("{BUSTLE}.xz"))?;
+    let output = Command::cargo_bin(PRG)?
+        .write_stdin(input)
+        .args(["-z", "-"])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    assert_eq!(output.stdout, fs::read(BUSTLE)?);
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn decompress_slow_stdin() -> Result<()> {
+    // 파이프로 첫 바이트만 먼저 와도 압축 형식을 알아봐야 함
+    use std::io::Write;
+    use std::process::Stdio;
+
+    let input = fs::read(format!("{BUSTLE}.gz"))?;
+    let mut child = std::process::Command::new(assert_cmd::cargo::cargo_bin(PRG))
+        .arg("-z")
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()?;
+    let mut stdin = child.stdin.take().unwrap();
+    stdin.write_all(&input[..1])?;
+    stdin.flush()?;
+    std::thread::sleep(std::time::Duration::from_millis(200));
+    stdin.write_all(&input[1..])?;
+    drop(stdin);
+    let output = child.wait_with_output()?;
+    assert!(output.status.success());
+    assert_eq!(output.stdout, fs::read(BUSTLE)?);
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn compressed_without_z() -> Result<()> {
+    let file = format!("{BUSTLE}.gz");
+    run_bytes(&[&file], &file)
+}
+
+// --------------------------------------------------
+#[test]
+fn pretty_piped() -> Result<()> {
+    // 테스트의 stdout 은 파이프이므로 --pretty 가 없는 것과 같아야 함
+    run_bytes(&["--pretty", BINARY], BINARY)?;
+    run(&["--pretty", FOX, SPIDERS, BUSTLE], "tests/expected/all.out")?;
+    run(&["--pretty", "-n", FOX, SPIDERS, BUSTLE], "tests/expected/all.n.out")?;
+    run(&["--pretty", "-b", BUSTLE], "tests/expected/the-bustle.txt.b.out")
+}
+
+// --------------------------------------------------
+#[test]
+fn dies_pretty_with_show_all() -> Result<()> {
+    Command::cargo_bin(PRG)?
+        .args(["--pretty", "-A", FOX])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("cannot be used with"));
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn reverse() -> Result<()> {
+    run(&["--reverse", BUSTLE], "tests/expected/the-bustle.txt.reverse.out")?;
+    run(&["--reverse", FOX, SPIDERS, BUSTLE], "tests/expected/all.reverse.out")?;
+    run(&["--reverse", EMPTY], "tests/expected/empty.txt.out")
+}
+
+// --------------------------------------------------
+#[test]
+fn reverse_no_final_newline() -> Result<()> {
+    run(&["--reverse", SPECIAL], "tests/expected/special.txt.reverse.out")
+}
+
+// --------------------------------------------------
+#[test]
+fn reverse_separator() -> Result<()> {
+    run(
+        &["--reverse", "--separator", ", ", SPIDERS],
+        "tests/expected/spiders.txt.reverse.comma.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn reverse_stdin_n() -> Result<()> {
+    run_stdin(BUSTLE, &["--reverse", "-n"], "tests/expected/the-bustle.txt.reverse.n.out")?;
+    run(&["--reverse", "-z", &format!("{BUSTLE}.xz")], "tests/expected/the-bustle.txt.reverse.out")
+}
+
+// --------------------------------------------------
+#[test]
+fn reverse_large() -> Result<()> {
+    // 블록 여러 개에 걸치고, 여러 바이트 구분자가 블록 경계에 걸치는 경우도 생기는 크기
+    let records: Vec<String> = (0..100_000).map(|i| format!("record {i}<>")).collect();
+    let mut file = tempfile::NamedTempFile::new()?;
+    std::io::Write::write_all(&mut file, records.concat().as_bytes())?;
+    let expected: String = records.iter().rev().map(String::as_str).collect();
+    Command::cargo_bin(PRG)?
+        .args(["--reverse", "--separator", "<>"])
+        .arg(file.path())
+        .assert()
+        .success()
+        .stdout(expected);
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn reverse_overlapping_separator() -> Result<()> {
+    // GNU tac 은 8 KiB 조각의 맨 앞에서 찾은 구분자와 겹치는 구분자도 찾음
+    //   tac -s aa 로 "x...x" (8191 바이트) + "aaa" + "y" 를 뒤집으면 "y" "a" "x...xaa"
+    let mut file = tempfile::NamedTempFile::new()?;
+    std::io::Write::write_all(&mut file, format!("{}aaay", "x".repeat(8191)).as_bytes())?;
+    Command::cargo_bin(PRG)?
+        .args(["--reverse", "--separator", "aa"])
+        .arg(file.path())
+        .assert()
+        .success()
+        .stdout(format!("ya{}aa", "x".repeat(8191)));
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn dies_separator_without_reverse() -> Result<()> {
+    Command::cargo_bin(PRG)?
+        .args(["--separator", ",", FOX])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("--reverse"));
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn follow() -> Result<()> {
+    use std::io::{BufRead, BufReader, Write};
+    use std::process::Stdio;
+    use std::sync::mpsc;
+    use std::time::Duration;
+
+    let dir = tempfile::tempdir()?;
+    let log = dir.path().join("app.log");
+    fs::write(&log, "one\ntwo\n")?;
+    let mut child = std::process::Command::new(assert_cmd::cargo::cargo_bin(PRG))
+        .args(["--follow", "-n"])
+        .arg(&log)
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()?;
+
+    // 출력은 끝나지 않으므로 별도 스레드에서 한 줄씩 읽음
+    let stdout = child.stdout.take().unwrap();
+    let (tx, rx) = mpsc::channel();
+    std::thread::spawn(move || {
+        for line in BufReader::new(stdout).lines().map_while(|l| l.ok()) {
+            let _ = tx.send(line);
+        }
+    });
+    let next = || rx.recv_timeout(Duration::from_secs(10));
+    let append = |text: &str| -> Result<()> {
+        let mut file = fs::OpenOptions::new().append(true).open(&log)?;
+        Ok(file.write_all(text.as_bytes())?)
+    };
+
+    assert_eq!(next()?, "     1\tone");
+    assert_eq!(next()?, "     2\ttwo");
+    // 줄이 끝나기 전에는 출력하지 않음
+    append("thr")?;
+    append("ee\n")?;
+    assert_eq!(next()?, "     3\tthree");
+    // 잘리면 처음부터, 번호는 이어서
+    fs::write(&log, "")?;
+    append("truncated\n")?;
+    assert_eq!(next()?, "     4\ttruncated");
+    // 잘린 뒤 바로 예전보다 길게 다시 써도 처음부터
+    fs::write(&log, "rewritten after truncate\n")?;
+    assert_eq!(next()?, "     5\trewritten after truncate");
+    // 로테이션되면 예전 파일에 덧붙인 줄까지 읽고 새 파일을 처음부터
+    append("last before rotate\n")?;
+    fs::rename(&log, dir.path().join("app.log.1"))?;
+    fs::write(&log, "rotated\n")?;
+    assert_eq!(next()?, "     6\tlast before rotate");
+    assert_eq!(next()?, "     7\trotated");
+
+    child.kill()?;
+    child.wait()?;
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn dies_follow_not_one_file() -> Result<()> {
+    for args in [&["--follow", FOX, SPIDERS][..], &["--follow"], &["--follow", "-"]] {
+        Command::cargo_bin(PRG)?
+            .args(args)
+            .assert()
+            .failure()
+            .stderr("catr: --follow needs exactly one FILE, not stdin\n");
+    }
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn lines() -> Result<()> {
+    run(&["--lines", "3:5", "-n", BUSTLE], "tests/expected/the-bustle.txt.lines.3-5.n.out")?;
+    run(&["--lines", "7:", BUSTLE], "tests/expected/the-bustle.txt.lines.7-.out")?;
+    run(&["--lines", ":1", FOX], "tests/expected/fox.txt.out")?;
+    run(&["--lines", "100:", FOX], "tests/expected/empty.txt.out")
+}
+
+// --------------------------------------------------
+#[test]
+fn lines_numbering_across_files() -> Result<()> {
+    // 두 번째 줄만 골라도 번호는 두 파일에 걸친 원래 번호
+    run(
+        &["-n", "--lines", "2", SPIDERS, BUSTLE],
+        "tests/expected/spiders.bustle.lines.2.n.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn from_to() -> Result<()> {
+    run(
+        &["--from", "morning", "--to", "^$", "-n", BUSTLE],
+        "tests/expected/the-bustle.txt.from-to.n.out",
+    )?;
+    run(&["--to", "solemn", BUSTLE], "tests/expected/the-bustle.txt.to.out")?;
+    run(&["--from", "no such line", BUSTLE], "tests/expected/empty.txt.out")
+}
+
+// --------------------------------------------------
+#[test]
+fn lines_from_to() -> Result<()> {
+    // 정규식은 --lines 안에서만 찾으므로 1 번 줄의 "The" 에서 끝나지 않음
+    run(
+        &["--lines", "3:9", "--to", "^The", "-n", BUSTLE],
+        "tests/expected/the-bustle.txt.lines.3-9.to.n.out",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn lines_stdin() -> Result<()> {
+    // 범위가 끝나면 나머지는 읽지 않으므로 끝나지 않는 입력도 바로 끝남
+    use std::io::Write;
+    use std::process::Stdio;
+    use std::time::{Duration, Instant};
+
+    let mut child = std::process::Command::new(assert_cmd::cargo::cargo_bin(PRG))
+        .args(["--lines", "2:3", "-n"])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()?;
+    let mut stdin = child.stdin.take().unwrap();
+    std::thread::spawn(move || {
+        // catr 가 끝나면 파이프가 닫혀서 쓰기가 실패함
+        for i in 1.. {
+            if writeln!(stdin, "{i}").is_err() {
+                break;
+            }
+        }
+    });
+    let deadline = Instant::now() + Duration::from_secs(10);
+    while child.try_wait()?.is_none() {
+        if Instant::now() > deadline {
+            child.kill()?;
+            panic!("catr kept reading after the last selected line");
+        }
+        std::thread::sleep(Duration::from_millis(10));
+    }
+    let output = child.wait_with_output()?;
+    assert!(output.status.success());
+    assert_eq!(String::from_utf8(output.stdout)?, "     2\t2\n     3\t3\n");
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn dies_bad_lines() -> Result<()> {
+    for range in ["0:3", "5:3", "x", ""] {
+        Command::cargo_bin(PRG)?
+            .args(["--lines", range, FOX])
+            .assert()
+            .failure()
+            .stderr(predicate::str::contains("--lines"));
+    }
+    Ok(())
+}
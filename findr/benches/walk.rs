@@ -0,0 +1,78 @@
+use criterion::{criterion_group, criterion_main, Criterion};
+use std::{env, fs, path::Path, process::Command, process::Stdio};
+use tempfile::TempDir;
+
+const PRG: &str = env!("CARGO_BIN_EXE_findr");
+
+// 1M entries by default, e.g. FINDR_BENCH_ENTRIES=100000 for a quick run
+fn num_entries() -> usize {
+    env::var("FINDR_BENCH_ENTRIES")
+        .ok()
+        .and_then(|n| n.parse().ok())
+        .unwrap_or(1_000_000)
+}
+
+// --------------------------------------------------
+// root/000/000/00.txt ... : 100 directories of 100 directories,
+// each holding up to 98 files, until `n` entries exist.
+fn gen_tree(n: usize) -> std::io::Result<TempDir> {
+    let dir = tempfile::tempdir()?;
+    let mut count = 0;
+    'outer: for i in 0.. {
+        let top = dir.path().join(format!("{i:03}"));
+        fs::create_dir(&top)?;
+        count += 1;
+        for j in 0..100 {
+            let leaf = top.join(format!("{j:03}"));
+            fs::create_dir(&leaf)?;
+            count += 1;
+            for k in 0..98 {
+                if count >= n {
+                    break 'outer;
+                }
+                fs::write(leaf.join(format!("{k:02}.txt")), "")?;
+                count += 1;
+            }
+        }
+    }
+    Ok(dir)
+}
+
+// --------------------------------------------------
+fn findr(root: &Path, args: &[&str]) {
+    let status = Command::new(PRG)
+        .arg(root)
+        .args(args)
+        .stdout(Stdio::null())
+        .status()
+        .expect("failed to run findr");
+    assert!(status.success());
+}
+
+// --------------------------------------------------
+fn bench_walk(c: &mut Criterion) {
+    let tree = gen_tree(num_entries()).expect("failed to generate tree");
+    let root = tree.path();
+
+    let mut group = c.benchmark_group("walk");
+    group.sample_size(10);
+    group.bench_function("sequential", |b| b.iter(|| findr(root, &[])));
+    for threads in ["2", "4", "8"] {
+        group.bench_function(format!("threads/{threads}"), |b| {
+            b.iter(|| findr(root, &["--threads", threads]))
+        });
+    }
+    group.bench_function("threads/8/ordered", |b| {
+        b.iter(|| findr(root, &["--threads", "8", "--ordered"]))
+    });
+    group.bench_function("sequential/name", |b| {
+        b.iter(|| findr(root, &["-n", "7[.]txt$"]))
+    });
+    group.bench_function("threads/8/name", |b| {
+        b.iter(|| findr(root, &["-n", "7[.]txt$", "--threads", "8"]))
+    });
+    group.finish();
+}
+
+criterion_group!(benches, bench_walk);
+criterion_main!(benches);
@@ -0,0 +1,185 @@
+use crate::matcher::EntryType;
+use crate::osstr;
+use crate::walk::Entry;
+use chrono::{Local, NaiveDate, TimeZone};
+use flate2::read::GzDecoder;
+use std::fs::File;
+use std::io::{self, BufReader, Read};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Separates the archive from the path inside it, as in `a.zip!/dir/file`.
+const SEPARATOR: &[u8] = b"!/";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Format {
+    Tar,
+    TarGz,
+    Zip,
+}
+
+/// A file, directory or link stored in an archive (`--search-archives`).
+#[derive(Debug, Clone)]
+pub struct Member {
+    archive: PathBuf,
+    /// The archive below the search path, or its name if it is the search
+    /// path itself.
+    archive_relative: PathBuf,
+    /// The path inside the archive, without a leading `./` or `/` or a
+    /// trailing `/`.
+    inner: Vec<u8>,
+    /// `None` for types findr doesn't know.
+    pub entry_type: Option<EntryType>,
+    pub size: u64,
+    pub mtime: Option<SystemTime>,
+}
+
+impl Member {
+    /// `archive!/path/in/archive`
+    pub fn path(&self) -> PathBuf {
+        join(&self.archive, &self.inner)
+    }
+
+    /// Like `path`, starting below the search path.
+    pub fn relative_path(&self) -> PathBuf {
+        join(&self.archive_relative, &self.inner)
+    }
+
+    /// The path of the archive itself.
+    pub fn archive(&self) -> &Path {
+        &self.archive
+    }
+
+    /// Like `path`, with `archive` as the archive's path.
+    pub fn path_under(&self, archive: &Path) -> PathBuf {
+        join(archive, &self.inner)
+    }
+
+    /// The last component of the path inside the archive.
+    pub fn file_name(&self) -> &[u8] {
+        let start = self.inner.iter().rposition(|&b| b == b'/').map_or(0, |i| i + 1);
+        &self.inner[start..]
+    }
+}
+
+fn join(archive: &Path, inner: &[u8]) -> PathBuf {
+    let mut path = osstr::to_bytes(archive.as_os_str()).into_owned();
+    path.extend_from_slice(SEPARATOR);
+    path.extend_from_slice(inner);
+    PathBuf::from(osstr::from_bytes(path))
+}
+
+/// Whether `entry` is a regular file named like an archive findr can read:
+/// `.tar`, `.tar.gz`, `.tgz` or `.zip`.
+pub fn is_archive(entry: &Entry) -> bool {
+    entry.file_type().is_file() && format(entry.file_name()).is_some()
+}
+
+fn format(name: &std::ffi::OsStr) -> Option<Format> {
+    let name = name.to_string_lossy().to_lowercase();
+    if name.ends_with(".tar") {
+        Some(Format::Tar)
+    } else if name.ends_with(".tar.gz") || name.ends_with(".tgz") {
+        Some(Format::TarGz)
+    } else if name.ends_with(".zip") {
+        Some(Format::Zip)
+    } else {
+        None
+    }
+}
+
+/// Adds every member of the archive at `entry` to `members`, in archive
+/// order. Archives inside the archive are not opened.
+///
+/// A damaged archive is read up to the damage: the members before it are
+/// added and the error is returned.
+pub fn members(entry: &Entry, members: &mut Vec<Member>) -> io::Result<()> {
+    let Some(format) = format(entry.file_name()) else {
+        return Ok(());
+    };
+    let archive_relative = match entry.depth() {
+        0 => PathBuf::from(entry.file_name()),
+        _ => entry.relative_path().to_path_buf(),
+    };
+    let member = |inner: &[u8], entry_type, size, mtime| {
+        let inner = normalize(inner);
+        (!inner.is_empty()).then(|| Member {
+            archive: entry.path().to_path_buf(),
+            archive_relative: archive_relative.clone(),
+            inner: inner.to_vec(),
+            entry_type,
+            size,
+            mtime,
+        })
+    };
+
+    let file = BufReader::new(File::open(entry.path())?);
+    match format {
+        Format::Tar => tar_members(file, member, members)?,
+        Format::TarGz => tar_members(GzDecoder::new(file), member, members)?,
+        Format::Zip => {
+            let mut zip = zip::ZipArchive::new(file)?;
+            for i in 0..zip.len() {
+                let file = zip.by_index_raw(i)?;
+                let entry_type = match () {
+                    _ if file.is_dir() => EntryType::Dir,
+                    _ if file.is_symlink() => EntryType::Link,
+                    _ => EntryType::File,
+                };
+                let mtime = file.last_modified().and_then(|t| {
+                    // zip 의 시각은 시간대 정보 없는 로컬 시각
+                    let date = NaiveDate::from_ymd_opt(t.year().into(), t.month().into(), t.day().into())?;
+                    let time = date.and_hms_opt(t.hour().into(), t.minute().into(), t.second().into())?;
+                    Some(SystemTime::from(Local.from_local_datetime(&time).earliest()?))
+                });
+                members.extend(member(file.name_raw(), Some(entry_type), file.size(), mtime));
+            }
+        }
+    }
+    Ok(())
+}
+
+fn tar_members(
+    reader: impl Read,
+    member: impl Fn(&[u8], Option<EntryType>, u64, Option<SystemTime>) -> Option<Member>,
+    members: &mut Vec<Member>,
+) -> io::Result<()> {
+    let mut tar = tar::Archive::new(reader);
+    for file in tar.entries()? {
+        let file = file?;
+        let header = file.header();
+        let kind = header.entry_type();
+        let entry_type = match () {
+            _ if kind.is_dir() => Some(EntryType::Dir),
+            _ if kind.is_file() || kind.is_contiguous() || kind.is_hard_link() => Some(EntryType::File),
+            _ if kind.is_symlink() => Some(EntryType::Link),
+            _ if kind.is_fifo() => Some(EntryType::Fifo),
+            _ if kind.is_character_special() => Some(EntryType::CharDevice),
+            _ if kind.is_block_special() => Some(EntryType::BlockDevice),
+            _ => None,
+        };
+        let mtime = header.mtime().ok().map(|secs| UNIX_EPOCH + Duration::from_secs(secs));
+        members.extend(member(&file.path_bytes(), entry_type, file.size(), mtime));
+    }
+    Ok(())
+}
+
+/// Strips leading `./` and `/` and trailing `/` from a path in an archive.
+fn normalize(mut path: &[u8]) -> &[u8] {
+    loop {
+        if let Some(rest) = path.strip_prefix(b"./") {
+            path = rest;
+        } else if let Some(rest) = path.strip_prefix(b"/") {
+            path = rest;
+        } else {
+            break;
+        }
+    }
+    while let Some(rest) = path.strip_suffix(b"/") {
+        path = rest;
+    }
+    if path == b"." {
+        return b"";
+    }
+    path
+}
@@ -0,0 +1,68 @@
+use regex::bytes::Regex;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, Read, Seek};
+use walkdir::DirEntry;
+
+/// How much of a file is checked for NUL bytes to tell binary files apart,
+/// the same heuristic git and grep use.
+const BINARY_CHECK_LEN: u64 = 8 * 1024;
+
+/// The first line of a file that matched.
+#[derive(Debug)]
+pub struct LineMatch {
+    /// 1-based
+    pub line_number: u64,
+    /// Without the line terminator.
+    pub line: Vec<u8>,
+}
+
+/// Searches the contents of regular files (`--contains`).
+pub struct ContentMatcher {
+    pub regex: Regex,
+    pub max_filesize: Option<u64>,
+}
+
+impl ContentMatcher {
+    /// The first line of `entry` matching the regex.
+    ///
+    /// Only regular files are searched. Files over `max_filesize` and binary
+    /// files never match.
+    pub fn first_match(&self, entry: &DirEntry) -> io::Result<Option<LineMatch>> {
+        if !entry.file_type().is_file() {
+            return Ok(None);
+        }
+        if let Some(max) = self.max_filesize {
+            if entry.metadata()?.len() > max {
+                return Ok(None);
+            }
+        }
+
+        let mut file = File::open(entry.path())?;
+        let mut head = Vec::new();
+        (&mut file).take(BINARY_CHECK_LEN).read_to_end(&mut head)?;
+        if head.contains(&0) {
+            return Ok(None);
+        }
+        file.rewind()?;
+
+        let mut reader = BufReader::new(file);
+        let mut line = Vec::new();
+        let mut line_number = 0;
+        loop {
+            line.clear();
+            if reader.read_until(b'\n', &mut line)? == 0 {
+                return Ok(None);
+            }
+            line_number += 1;
+            if line.ends_with(b"\n") {
+                line.pop();
+                if line.ends_with(b"\r") {
+                    line.pop();
+                }
+            }
+            if self.regex.is_match(&line) {
+                return Ok(Some(LineMatch { line_number, line }));
+            }
+        }
+    }
+}
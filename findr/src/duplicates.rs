@@ -0,0 +1,175 @@
+use crate::report;
+use findr::osstr;
+use rayon::prelude::*;
+use std::collections::{HashMap, HashSet};
+use std::fs::{self, File};
+use std::hash::Hash;
+use std::io::{self, BufRead, Read, Write};
+use std::path::Path;
+use walkdir::DirEntry;
+
+/// Bytes read for the cheap first pass over same sized files.
+const PARTIAL_LEN: u64 = 4096;
+
+/// What to do with the duplicates found (`--hardlink`, `--delete-dupes`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Action {
+    List,
+    Hardlink,
+    Delete,
+}
+
+/// Groups of regular files with identical contents.
+///
+/// Files are grouped by size first, then by a hash of their first 4 KiB and
+/// only then by a hash of the whole file, so most files are never read in
+/// full. Empty files, extra hard links to a file already seen and files
+/// reached again through overlapping search paths are left out. Each group
+/// is sorted by path, and the first file of a group is the one that is kept.
+pub fn find(entries: Vec<DirEntry>) -> Vec<Vec<DirEntry>> {
+    let mut by_size: HashMap<u64, Vec<DirEntry>> = HashMap::new();
+    let mut seen = HashSet::new();
+    for entry in entries {
+        if !entry.file_type().is_file() {
+            continue;
+        }
+        let md = match entry.metadata() {
+            Ok(md) => md,
+            Err(e) => {
+                report::error(e);
+                continue;
+            }
+        };
+        if md.len() == 0 {
+            continue;
+        }
+        let id = match file_id(&entry, &md) {
+            Ok(id) => id,
+            Err(e) => {
+                report::error(format_args!("{}: {e}", entry.path().display()));
+                continue;
+            }
+        };
+        if !seen.insert(id) {
+            continue;
+        }
+        by_size.entry(md.len()).or_default().push(entry);
+    }
+
+    let groups = by_size.into_values().filter(|g| g.len() > 1).collect();
+    let groups = split_by(groups, |path| hash_file(path, Some(PARTIAL_LEN)));
+    let mut groups = split_by(groups, |path| hash_file(path, None));
+    for group in &mut groups {
+        group.sort_by(|a, b| a.path().cmp(b.path()));
+    }
+    groups.sort_by(|a, b| a[0].path().cmp(b[0].path()));
+    groups
+}
+
+/// Splits every group by `key`, hashing files in parallel, and drops the
+/// files that turn out to be unique.
+fn split_by<K, F>(groups: Vec<Vec<DirEntry>>, key: F) -> Vec<Vec<DirEntry>>
+where
+    K: Hash + Eq + Send,
+    F: Fn(&Path) -> io::Result<K> + Sync,
+{
+    groups
+        .into_par_iter()
+        .flat_map_iter(|group| {
+            let mut by_key: HashMap<K, Vec<DirEntry>> = HashMap::new();
+            for entry in group {
+                match key(entry.path()) {
+                    Ok(k) => by_key.entry(k).or_default().push(entry),
+                    Err(e) => report::error(format_args!("{}: {e}", entry.path().display())),
+                }
+            }
+            by_key.into_values().filter(|g| g.len() > 1)
+        })
+        .collect()
+}
+
+/// xxh3 of the first `limit` bytes, or blake3 of the whole file.
+fn hash_file(path: &Path, limit: Option<u64>) -> io::Result<[u8; 32]> {
+    let file = File::open(path)?;
+    match limit {
+        Some(limit) => {
+            let mut head = Vec::with_capacity(limit as usize);
+            file.take(limit).read_to_end(&mut head)?;
+            let mut hash = [0; 32];
+            hash[..16].copy_from_slice(&xxhash_rust::xxh3::xxh3_128(&head).to_le_bytes());
+            Ok(hash)
+        }
+        None => {
+            let mut hasher = blake3::Hasher::new();
+            hasher.update_reader(file)?;
+            Ok(*hasher.finalize().as_bytes())
+        }
+    }
+}
+
+/// What makes two entries the same file: the inode, or without inode
+/// numbers the canonical path, so that `findr . . --duplicates` doesn't take
+/// a file it reached twice for a duplicate of itself.
+#[cfg(unix)]
+fn file_id(_entry: &DirEntry, md: &fs::Metadata) -> io::Result<(u64, u64)> {
+    use std::os::unix::fs::MetadataExt;
+    Ok((md.dev(), md.ino()))
+}
+
+#[cfg(not(unix))]
+fn file_id(entry: &DirEntry, _md: &fs::Metadata) -> io::Result<std::path::PathBuf> {
+    fs::canonicalize(entry.path())
+}
+
+/// Prints the groups one path per line, with a blank line between groups.
+pub fn print(out: &mut impl Write, groups: &[Vec<DirEntry>]) -> io::Result<()> {
+    for (i, group) in groups.iter().enumerate() {
+        if i > 0 {
+            out.write_all(b"\n")?;
+        }
+        for entry in group {
+            osstr::write_path(out, entry.path())?;
+        }
+    }
+    Ok(())
+}
+
+/// Asks on stderr and reads the answer from stdin; only `y`/`yes` agree.
+pub fn confirm(question: &str) -> io::Result<bool> {
+    eprint!("{question} [y/N] ");
+    let mut answer = String::new();
+    io::stdin().lock().read_line(&mut answer)?;
+    Ok(matches!(answer.trim().to_lowercase().as_str(), "y" | "yes"))
+}
+
+/// Replaces or deletes every file but the first of each group. Failures are
+/// reported and skipped; returns how many files were handled.
+pub fn apply(action: Action, groups: &[Vec<DirEntry>]) -> usize {
+    let mut done = 0;
+    for group in groups {
+        let (keep, dupes) = group.split_first().expect("groups have 2+ files");
+        for dupe in dupes {
+            let result = match action {
+                Action::List => Ok(()),
+                Action::Hardlink => hardlink(keep.path(), dupe.path()),
+                Action::Delete => fs::remove_file(dupe.path()),
+            };
+            match result {
+                Ok(()) => done += 1,
+                Err(e) => report::error(format_args!("{}: {e}", dupe.path().display())),
+            }
+        }
+    }
+    done
+}
+
+/// Makes `dupe` a hard link to `keep`. The link is created next to `dupe`
+/// and renamed over it, so `dupe` is never missing.
+fn hardlink(keep: &Path, dupe: &Path) -> io::Result<()> {
+    let mut tmp = dupe.as_os_str().to_os_string();
+    tmp.push(".findr-tmp");
+    fs::hard_link(keep, &tmp)?;
+    fs::rename(&tmp, dupe).inspect_err(|_| {
+        let _ = fs::remove_file(&tmp);
+    })
+}
@@ -0,0 +1,508 @@
+use crate::report;
+use chrono::{DateTime, Local};
+use findr::matcher::{time_of, TimeKind};
+use findr::osstr;
+use findr::walk::Entry;
+use serde_json::json;
+use std::fs::{self, FileType, Metadata};
+use std::io::{self, Write};
+use std::path::Path;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use walkdir::DirEntry;
+
+/// strftime(3) conversions accepted after `%A`, `%C` and `%T`, besides the
+/// find specific `@` (seconds since the epoch) and `+` (date+time).
+const TIME_FIELDS: &str = "aAbBcdDeFhHIjklmMprRsStTuUVwWxXyYzZ";
+
+/// How each entry is printed.
+#[derive(Debug, Clone, Default)]
+pub enum OutputFormat {
+    /// The path only
+    #[default]
+    Path,
+    /// `find -ls`
+    Ls,
+    /// `find -printf`
+    Printf(PrintfFormat),
+    /// One JSON object per line
+    Json,
+}
+
+/// A parsed `--printf` format.
+#[derive(Debug, Clone)]
+pub struct PrintfFormat(Vec<Piece>);
+
+#[derive(Debug, Clone)]
+enum Piece {
+    Literal(Vec<u8>),
+    Directive(Spec, Directive),
+}
+
+/// Field width of a directive, e.g. `%-10p`.
+#[derive(Debug, Clone, Copy, Default)]
+struct Spec {
+    left: bool,
+    width: usize,
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Directive {
+    Path,
+    Name,
+    Dir,
+    StartingPoint,
+    RelPath,
+    Depth,
+    Size,
+    KBlocks,
+    Blocks,
+    OctalMode,
+    SymbolicMode,
+    User,
+    Group,
+    Uid,
+    Gid,
+    Links,
+    Inode,
+    LinkTarget,
+    Type,
+    TargetType,
+    /// `%a`, `%c` and `%t`: ctime(3) style
+    Time(TimeKind),
+    /// `%Ak`, `%Ck` and `%Tk`
+    TimeField(TimeKind, char),
+}
+
+impl PrintfFormat {
+    /// Parses the format with find's directives and backslash escapes.
+    pub fn parse(format: &str) -> Result<PrintfFormat, String> {
+        let mut pieces = vec![];
+        let mut literal = vec![];
+        let mut chars = format.chars().peekable();
+        while let Some(c) = chars.next() {
+            match c {
+                '\\' => {
+                    let escaped = match chars.next() {
+                        Some('n') => '\n',
+                        Some('t') => '\t',
+                        Some('r') => '\r',
+                        Some('0') => '\0',
+                        Some('a') => '\x07',
+                        Some('f') => '\x0c',
+                        Some('v') => '\x0b',
+                        Some('\\') => '\\',
+                        Some(c) => return Err(format!("unknown escape '\\{c}'")),
+                        None => return Err("format ends with '\\'".to_string()),
+                    };
+                    literal.extend_from_slice(escaped.encode_utf8(&mut [0; 4]).as_bytes());
+                }
+                '%' => {
+                    if chars.peek() == Some(&'%') {
+                        chars.next();
+                        literal.push(b'%');
+                        continue;
+                    }
+                    let mut spec = Spec::default();
+                    if chars.peek() == Some(&'-') {
+                        chars.next();
+                        spec.left = true;
+                    }
+                    while let Some(d) = chars.peek().and_then(|c| c.to_digit(10)) {
+                        chars.next();
+                        spec.width = spec.width
+                            .checked_mul(10)
+                            .and_then(|w| w.checked_add(d as usize))
+                            .ok_or("field width too large")?;
+                    }
+                    let Some(c) = chars.next() else {
+                        return Err("format ends with '%'".to_string());
+                    };
+                    let directive = match c {
+                        'p' => Directive::Path,
+                        'f' => Directive::Name,
+                        'h' => Directive::Dir,
+                        'H' => Directive::StartingPoint,
+                        'P' => Directive::RelPath,
+                        'd' => Directive::Depth,
+                        's' => Directive::Size,
+                        'k' => Directive::KBlocks,
+                        'b' => Directive::Blocks,
+                        'm' => Directive::OctalMode,
+                        'M' => Directive::SymbolicMode,
+                        'u' => Directive::User,
+                        'g' => Directive::Group,
+                        'U' => Directive::Uid,
+                        'G' => Directive::Gid,
+                        'n' => Directive::Links,
+                        'i' => Directive::Inode,
+                        'l' => Directive::LinkTarget,
+                        'y' => Directive::Type,
+                        'Y' => Directive::TargetType,
+                        'a' => Directive::Time(TimeKind::Access),
+                        'c' => Directive::Time(TimeKind::Change),
+                        't' => Directive::Time(TimeKind::Modify),
+                        'A' | 'C' | 'T' => {
+                            let kind = match c {
+                                'A' => TimeKind::Access,
+                                'C' => TimeKind::Change,
+                                _ => TimeKind::Modify,
+                            };
+                            match chars.next() {
+                                Some(k) if k == '@' || k == '+' || TIME_FIELDS.contains(k) => {
+                                    Directive::TimeField(kind, k)
+                                }
+                                Some(k) => return Err(format!("unknown time field '%{c}{k}'")),
+                                None => return Err(format!("missing time field after '%{c}'")),
+                            }
+                        }
+                        c => return Err(format!("unknown directive '%{c}'")),
+                    };
+                    if !literal.is_empty() {
+                        pieces.push(Piece::Literal(std::mem::take(&mut literal)));
+                    }
+                    pieces.push(Piece::Directive(spec, directive));
+                }
+                c => literal.extend_from_slice(c.encode_utf8(&mut [0; 4]).as_bytes()),
+            }
+        }
+        if !literal.is_empty() {
+            pieces.push(Piece::Literal(literal));
+        }
+        Ok(PrintfFormat(pieces))
+    }
+}
+
+/// Writes entries in the chosen [`OutputFormat`].
+pub struct Printer {
+    format: OutputFormat,
+    #[cfg(unix)]
+    users: uzers::UsersCache,
+}
+
+impl Printer {
+    pub fn new(format: OutputFormat) -> Printer {
+        Printer {
+            format,
+            #[cfg(unix)]
+            users: uzers::UsersCache::new(),
+        }
+    }
+
+    /// Writes one entry. Entries whose metadata can't be read are reported
+    /// on stderr and skipped.
+    pub fn write(&self, out: &mut impl Write, entry: &Entry) -> io::Result<()> {
+        if let OutputFormat::Path = self.format {
+            return osstr::write_path(out, entry.path());
+        }
+        let metadata = match entry.metadata() {
+            Ok(m) => m,
+            Err(e) => {
+                report::error(e);
+                return Ok(());
+            }
+        };
+        match &self.format {
+            OutputFormat::Path => unreachable!(),
+            OutputFormat::Ls => self.write_ls(out, entry, &metadata),
+            OutputFormat::Printf(format) => self.write_printf(out, format, entry, &metadata),
+            OutputFormat::Json => write_json(out, entry, &metadata),
+        }
+    }
+
+    // inode, 1K blocks, permissions, links, owner, group, size, mtime, path
+    fn write_ls(&self, out: &mut impl Write, entry: &DirEntry, md: &Metadata) -> io::Result<()> {
+        let mtime = DateTime::<Local>::from(md.modified().unwrap_or(UNIX_EPOCH));
+        let age = SystemTime::now()
+            .duration_since(md.modified().unwrap_or(UNIX_EPOCH))
+            .ok();
+        // like ls, the year replaces the time for files older than 6 months
+        let recent = age.is_some_and(|age| age < Duration::from_secs(60 * 60 * 24 * 182));
+        let mtime = if recent {
+            mtime.format("%b %e %H:%M")
+        } else {
+            mtime.format("%b %e  %Y")
+        };
+        write!(
+            out,
+            "{:>9} {:>6} {} {:>3} {:<8} {:<8} {:>8} {} ",
+            inode(md),
+            kblocks(md),
+            symbolic_mode(md),
+            nlink(md),
+            self.user(md),
+            self.group(md),
+            md.len(),
+            mtime,
+        )?;
+        out.write_all(&osstr::to_bytes(entry.path().as_os_str()))?;
+        if entry.path_is_symlink() {
+            if let Ok(target) = fs::read_link(entry.path()) {
+                out.write_all(b" -> ")?;
+                out.write_all(&osstr::to_bytes(target.as_os_str()))?;
+            }
+        }
+        out.write_all(b"\n")
+    }
+
+    fn write_printf(
+        &self,
+        out: &mut impl Write,
+        format: &PrintfFormat,
+        entry: &Entry,
+        md: &Metadata,
+    ) -> io::Result<()> {
+        for piece in &format.0 {
+            let (spec, directive) = match piece {
+                Piece::Literal(bytes) => {
+                    out.write_all(bytes)?;
+                    continue;
+                }
+                Piece::Directive(spec, directive) => (spec, *directive),
+            };
+            let path = entry.path();
+            let value: Vec<u8> = match directive {
+                Directive::Path => bytes_of(path),
+                Directive::Name => osstr::to_bytes(entry.file_name()).into_owned(),
+                Directive::Dir => match path.parent() {
+                    Some(p) if !p.as_os_str().is_empty() => bytes_of(p),
+                    _ => b".".to_vec(),
+                },
+                Directive::StartingPoint => bytes_of(entry.starting_point()),
+                Directive::RelPath => bytes_of(entry.relative_path()),
+                Directive::Depth => entry.depth().to_string().into_bytes(),
+                Directive::Size => md.len().to_string().into_bytes(),
+                Directive::KBlocks => kblocks(md).to_string().into_bytes(),
+                Directive::Blocks => blocks(md).to_string().into_bytes(),
+                Directive::OctalMode => format!("{:o}", mode(md) & 0o7777).into_bytes(),
+                Directive::SymbolicMode => symbolic_mode(md).into_bytes(),
+                Directive::User => self.user(md).into_bytes(),
+                Directive::Group => self.group(md).into_bytes(),
+                Directive::Uid => uid(md).to_string().into_bytes(),
+                Directive::Gid => gid(md).to_string().into_bytes(),
+                Directive::Links => nlink(md).to_string().into_bytes(),
+                Directive::Inode => inode(md).to_string().into_bytes(),
+                Directive::LinkTarget => match fs::read_link(path) {
+                    Ok(target) if entry.path_is_symlink() => bytes_of(&target),
+                    _ => vec![],
+                },
+                Directive::Type => vec![type_char(entry.file_type()) as u8],
+                Directive::TargetType => match fs::metadata(path) {
+                    Ok(target) => vec![type_char(target.file_type()) as u8],
+                    // like find: N for a dangling link
+                    Err(_) => b"N".to_vec(),
+                },
+                Directive::Time(kind) => format_time(time_of(md, kind), '\0').into_bytes(),
+                Directive::TimeField(kind, k) => format_time(time_of(md, kind), k).into_bytes(),
+            };
+            write_padded(out, spec, &value)?;
+        }
+        Ok(())
+    }
+
+    #[cfg(unix)]
+    fn user(&self, md: &Metadata) -> String {
+        use uzers::Users;
+        match self.users.get_user_by_uid(uid(md)) {
+            Some(user) => user.name().to_string_lossy().into_owned(),
+            None => uid(md).to_string(),
+        }
+    }
+
+    #[cfg(unix)]
+    fn group(&self, md: &Metadata) -> String {
+        use uzers::Groups;
+        match self.users.get_group_by_gid(gid(md)) {
+            Some(group) => group.name().to_string_lossy().into_owned(),
+            None => gid(md).to_string(),
+        }
+    }
+
+    #[cfg(not(unix))]
+    fn user(&self, _md: &Metadata) -> String {
+        "-".to_string()
+    }
+
+    #[cfg(not(unix))]
+    fn group(&self, _md: &Metadata) -> String {
+        "-".to_string()
+    }
+}
+
+fn write_json(out: &mut impl Write, entry: &DirEntry, md: &Metadata) -> io::Result<()> {
+    let seconds = |t: io::Result<SystemTime>| {
+        t.ok()
+            .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+            .map(|d| d.as_secs_f64())
+    };
+    let value = json!({
+        // JSON strings are Unicode, so names that aren't UTF-8 are lossy here
+        "path": entry.path().to_string_lossy(),
+        "type": type_name(entry.file_type()),
+        "size": md.len(),
+        "mode": format!("{:04o}", mode(md) & 0o7777),
+        "mtime": seconds(md.modified()),
+        "atime": seconds(md.accessed()),
+        "ctime": seconds(Ok(time_of(md, TimeKind::Change))),
+    });
+    serde_json::to_writer(&mut *out, &value)?;
+    out.write_all(b"\n")
+}
+
+fn write_padded(out: &mut impl Write, spec: &Spec, value: &[u8]) -> io::Result<()> {
+    let pad = spec.width.saturating_sub(value.len());
+    if !spec.left {
+        out.write_all(&b" ".repeat(pad))?;
+    }
+    out.write_all(value)?;
+    if spec.left {
+        out.write_all(&b" ".repeat(pad))?;
+    }
+    Ok(())
+}
+
+fn bytes_of(path: &Path) -> Vec<u8> {
+    osstr::to_bytes(path.as_os_str()).into_owned()
+}
+
+/// `%Tk` and friends; `'\0'` is the ctime(3) style of `%t`.
+fn format_time(time: SystemTime, field: char) -> String {
+    let since_epoch = time.duration_since(UNIX_EPOCH).unwrap_or_default();
+    let time = DateTime::<Local>::from(time);
+    // find prints 10 fractional digits
+    let nanos = format!("{:09}0", since_epoch.subsec_nanos());
+    match field {
+        '\0' => format!("{}.{nanos} {}", time.format("%a %b %e %H:%M:%S"), time.format("%Y")),
+        '@' => format!("{}.{nanos}", since_epoch.as_secs()),
+        '+' => format!("{}.{nanos}", time.format("%Y-%m-%d+%H:%M:%S")),
+        'S' => format!("{}.{nanos}", time.format("%S")),
+        k => time.format(&format!("%{k}")).to_string(),
+    }
+}
+
+fn type_char(ft: FileType) -> char {
+    if ft.is_dir() {
+        return 'd';
+    }
+    if ft.is_symlink() {
+        return 'l';
+    }
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::FileTypeExt;
+        if ft.is_socket() {
+            return 's';
+        }
+        if ft.is_fifo() {
+            return 'p';
+        }
+        if ft.is_block_device() {
+            return 'b';
+        }
+        if ft.is_char_device() {
+            return 'c';
+        }
+    }
+    if ft.is_file() {
+        'f'
+    } else {
+        'U'
+    }
+}
+
+pub fn type_name(ft: FileType) -> &'static str {
+    match type_char(ft) {
+        'd' => "directory",
+        'l' => "symlink",
+        's' => "socket",
+        'p' => "fifo",
+        'b' => "block_device",
+        'c' => "char_device",
+        'f' => "file",
+        _ => "unknown",
+    }
+}
+
+/// `ls -l` style permissions, e.g. `drwxr-xr-x`.
+fn symbolic_mode(md: &Metadata) -> String {
+    let mode = mode(md);
+    let mut s = String::with_capacity(10);
+    s.push(match type_char(md.file_type()) {
+        'f' => '-',
+        c => c,
+    });
+    let special = [(0o4000, 's', 'S'), (0o2000, 's', 'S'), (0o1000, 't', 'T')];
+    for (i, (bit, set_x, set_no_x)) in special.iter().enumerate() {
+        let shift = 6 - 3 * i;
+        let rwx = (mode >> shift) & 0o7;
+        s.push(if rwx & 0o4 != 0 { 'r' } else { '-' });
+        s.push(if rwx & 0o2 != 0 { 'w' } else { '-' });
+        let x = rwx & 0o1 != 0;
+        s.push(match (mode & bit != 0, x) {
+            (true, true) => *set_x,
+            (true, false) => *set_no_x,
+            (false, true) => 'x',
+            (false, false) => '-',
+        });
+    }
+    s
+}
+
+#[cfg(unix)]
+mod meta {
+    use std::fs::Metadata;
+    use std::os::unix::fs::MetadataExt;
+
+    pub fn mode(md: &Metadata) -> u32 {
+        md.mode()
+    }
+    pub fn uid(md: &Metadata) -> u32 {
+        md.uid()
+    }
+    pub fn gid(md: &Metadata) -> u32 {
+        md.gid()
+    }
+    pub fn nlink(md: &Metadata) -> u64 {
+        md.nlink()
+    }
+    pub fn inode(md: &Metadata) -> u64 {
+        md.ino()
+    }
+    /// In 512 byte units
+    pub fn blocks(md: &Metadata) -> u64 {
+        md.blocks()
+    }
+}
+
+#[cfg(not(unix))]
+mod meta {
+    use std::fs::Metadata;
+
+    pub fn mode(md: &Metadata) -> u32 {
+        match (md.is_dir(), md.permissions().readonly()) {
+            (true, _) => 0o755,
+            (false, true) => 0o444,
+            (false, false) => 0o644,
+        }
+    }
+    pub fn uid(_md: &Metadata) -> u32 {
+        0
+    }
+    pub fn gid(_md: &Metadata) -> u32 {
+        0
+    }
+    pub fn nlink(_md: &Metadata) -> u64 {
+        1
+    }
+    pub fn inode(_md: &Metadata) -> u64 {
+        0
+    }
+    pub fn blocks(md: &Metadata) -> u64 {
+        md.len().div_ceil(512)
+    }
+}
+
+use meta::{blocks, gid, inode, mode, nlink, uid};
+
+fn kblocks(md: &Metadata) -> u64 {
+    blocks(md).div_ceil(2)
+}
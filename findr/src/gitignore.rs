@@ -0,0 +1,127 @@
+use crate::report;
+use ignore::gitignore::{Gitignore, GitignoreBuilder};
+use ignore::Match;
+use std::collections::HashMap;
+use std::path::{self, Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use walkdir::DirEntry;
+
+/// Ignore rules of a single directory.
+struct DirRules {
+    /// `.git/info/exclude`, `.gitignore` and `.ignore` of this directory,
+    /// added in that order so the later files take precedence.
+    matcher: Gitignore,
+    /// The directory contains `.git`, so rules above it do not apply.
+    is_repo_root: bool,
+    /// The directory is inside a git repository.
+    in_repo: bool,
+}
+
+/// Hierarchical `.gitignore` / `.ignore` rules, loaded lazily per directory
+/// while walking.
+///
+/// As in git, the deepest matching rule wins, and `.gitignore`,
+/// `.git/info/exclude` and the global excludes file are only honored inside
+/// a git repository. `.ignore` files are honored everywhere.
+pub struct IgnoreRules {
+    hidden: bool,
+    global: Gitignore,
+    dirs: Mutex<HashMap<PathBuf, Arc<DirRules>>>,
+}
+
+impl IgnoreRules {
+    /// `hidden` keeps dotfiles, which are skipped by default.
+    pub fn new(hidden: bool) -> IgnoreRules {
+        let (global, err) = Gitignore::global();
+        if let Some(e) = err {
+            report::other_error(e);
+        }
+        IgnoreRules {
+            hidden,
+            global,
+            dirs: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Whether the walk should skip `entry` (and everything below it).
+    /// Search roots are never skipped.
+    pub fn is_excluded(&self, entry: &DirEntry) -> bool {
+        entry.depth() > 0 && self.is_path_excluded(entry.path(), entry.file_type().is_dir())
+    }
+
+    /// Like `is_excluded`, for a path known not to be a search root.
+    pub fn is_path_excluded(&self, path: &Path, is_dir: bool) -> bool {
+        let name = path.file_name().unwrap_or_default().to_string_lossy();
+        if name == ".git" && is_dir {
+            return true;
+        }
+        if !self.hidden && name.starts_with('.') {
+            return true;
+        }
+        self.is_ignored(path, is_dir)
+    }
+
+    fn is_ignored(&self, path: &Path, is_dir: bool) -> bool {
+        let path = match path::absolute(path) {
+            Ok(p) => p,
+            Err(_) => return false,
+        };
+        let mut in_repo = false;
+        let mut dir = path.parent();
+        while let Some(d) = dir {
+            let rules = self.dir_rules(d);
+            in_repo |= rules.in_repo;
+            match rules.matcher.matched(&path, is_dir) {
+                Match::Ignore(_) => return true,
+                Match::Whitelist(_) => return false,
+                Match::None => {}
+            }
+            if rules.is_repo_root {
+                break;
+            }
+            dir = d.parent();
+        }
+        in_repo && self.global.matched(&path, is_dir).is_ignore()
+    }
+
+    fn dir_rules(&self, dir: &Path) -> Arc<DirRules> {
+        if let Some(rules) = self.dirs.lock().unwrap().get(dir) {
+            return Arc::clone(rules);
+        }
+        // Computed without holding the lock: the parent lookup recurses.
+        let is_repo_root = dir.join(".git").exists();
+        let in_repo = is_repo_root
+            || dir.parent().is_some_and(|p| self.dir_rules(p).in_repo);
+
+        let mut builder = GitignoreBuilder::new(dir);
+        let mut files = vec![];
+        if is_repo_root {
+            files.push(dir.join(".git/info/exclude"));
+        }
+        if in_repo {
+            files.push(dir.join(".gitignore"));
+        }
+        files.push(dir.join(".ignore"));
+        for f in files.iter().filter(|f| f.is_file()) {
+            // 잘못된 패턴은 그 줄만 빼고 나머지 규칙은 적용
+            if let Some(e) = builder.add(f) {
+                report::other_error(e);
+            }
+        }
+        let matcher = builder.build().unwrap_or_else(|e| {
+            report::other_error(e);
+            Gitignore::empty()
+        });
+
+        let rules = Arc::new(DirRules {
+            matcher,
+            is_repo_root,
+            in_repo,
+        });
+        self.dirs
+            .lock()
+            .unwrap()
+            .insert(dir.to_path_buf(), Arc::clone(&rules));
+        rules
+    }
+}
@@ -0,0 +1,48 @@
+//! Rust version of find, as a library.
+//!
+//! `find` walks the search paths and yields the entries a `Matcher`
+//! accepts:
+//!
+//! ```no_run
+//! use findr::matcher::{And, EntryType, Matcher, Name, Type};
+//! use regex::bytes::Regex;
+//!
+//! let matcher = And(vec![
+//!     Box::new(Type(EntryType::File)),
+//!     Box::new(Name(Regex::new(r"\.rs$").unwrap())),
+//! ]);
+//! for entry in findr::find(["src"], matcher) {
+//!     println!("{} {}", entry.depth(), entry.path().display());
+//! }
+//! ```
+//!
+//! The options, output formats and the rest of the command line tool live
+//! in the binary.
+
+pub mod archive;
+pub mod matcher;
+pub mod osstr;
+pub mod walk;
+
+use matcher::Matcher;
+use std::path::Path;
+use walk::{Entry, WalkOptions};
+
+/// The entries below `roots`, and the roots themselves, that `matcher`
+/// accepts, in walk order. They are the entries `Matcher::is_match` sees.
+///
+/// Symbolic links are not followed. Entries that can't be read are skipped;
+/// use `walk::WalkOptions` directly to see the errors.
+pub fn find<P, M>(roots: impl IntoIterator<Item = P>, matcher: M) -> impl Iterator<Item = Entry>
+where
+    P: AsRef<Path>,
+    M: Matcher,
+{
+    let options = WalkOptions::default();
+    let roots: Vec<_> = roots.into_iter().map(|p| p.as_ref().to_path_buf()).collect();
+    roots
+        .into_iter()
+        .flat_map(move |root| options.walkdir(root))
+        .filter_map(move |e| options.check(e, 0).ok())
+        .filter(move |e| matcher.is_match(e))
+}
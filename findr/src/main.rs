@@ -1,29 +1,33 @@
-use clap::{Parser, builder::PossibleValue, ValueEnum, ArgAction};
-use regex::Regex;
+use clap::{builder::PossibleValue, Parser, ValueEnum};
+use regex::bytes::{Regex, RegexBuilder};
 use anyhow::Result;
-use walkdir::WalkDir;
+use std::collections::BTreeSet;
+use std::io::{self, BufWriter, Write};
+use std::mem::take;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc;
+use std::thread;
+use std::time::Instant;
 
-#[derive(Debug, Eq, PartialEq, Clone)]
-enum EntryType {
-    Dir,
-    File,
-    Link,
-}
-
-// implement ValueEnum trait
-// https://docs.rs/clap/latest/clap/trait.ValueEnum.html
-impl ValueEnum for EntryType {
-    fn value_variants<'a>() -> &'a [Self] {
-        &[EntryType::Dir, EntryType::File, EntryType::Link]
-    }
-    fn to_possible_value(&self) -> Option<PossibleValue> {
-        Some(match self {
-            EntryType::Dir => PossibleValue::new("d"),
-            EntryType::File  => PossibleValue::new("f"),
-            EntryType::Link => PossibleValue::new("l"),
-        })
-    }
-}
+mod contents;
+mod duplicates;
+mod format;
+mod gitignore;
+mod parallel;
+mod report;
+mod sort;
+mod stats;
+mod watch;
+use contents::{ContentMatcher, LineMatch};
+use findr::archive::{self, Member};
+use findr::matcher::{self, And, EntryType, Matcher, Or};
+use findr::osstr;
+use findr::walk::{Entry, WalkOptions};
+use format::{OutputFormat, Printer, PrintfFormat};
+use gitignore::IgnoreRules;
+use sort::{Item, SortKey, SortScope, Sorter};
+use stats::Stats;
+use watch::{Change, Event, Watcher};
 
 #[derive(Parser,Debug)]
 #[command(about, version, author)]
@@ -34,8 +38,9 @@ struct Args {
         name = "PATH",
         default_value = ".",
         num_args(0..),
+        value_parser(clap::value_parser!(PathBuf)),
     )]
-    paths: Vec<String>,
+    paths: Vec<PathBuf>,
 
     /// Name
     #[arg(
@@ -54,75 +59,514 @@ struct Args {
         short('t'),
         long("type"),
         name = "TYPE",
-        value_parser(clap::value_parser!(EntryType)),
+        value_parser(clap::value_parser!(TypeArg)),
         num_args(0..),
     )]
-    entry_types: Vec<EntryType>,
+    entry_types: Vec<TypeArg>,
+
+    /// Path below the search path, e.g. 'src/.*/tests'
+    #[arg(long("path"), value_name = "PATH_REGEX", value_parser(Regex::new), num_args(0..))]
+    path_patterns: Vec<Regex>,
+
+    /// Like --path, but case insensitive
+    #[arg(long("ipath"), value_name = "PATH_REGEX", value_parser(parse_ipath), num_args(0..))]
+    ipath_patterns: Vec<Regex>,
+
+    /// Match --path and --ipath against absolute paths
+    #[arg(long)]
+    absolute_path: bool,
+
+    /// Respect .gitignore, .ignore, .git/info/exclude and the global excludes file
+    #[arg(long)]
+    gitignore: bool,
+
+    /// Include hidden files and directories in --gitignore mode
+    #[arg(long, requires("gitignore"))]
+    hidden: bool,
+
+    /// Walk directories in parallel on N threads
+    #[arg(
+        short('j'),
+        long,
+        value_name = "N",
+        value_parser(clap::builder::RangedU64ValueParser::<usize>::new().range(1..)),
+    )]
+    threads: Option<usize>,
+
+    /// Print parallel results sorted by path instead of as they are found
+    #[arg(long, requires("threads"), conflicts_with("sort"))]
+    ordered: bool,
+
+    /// Sort entries by KEY
+    #[arg(long, value_name = "KEY", value_enum)]
+    sort: Option<SortKey>,
+
+    /// Reverse the --sort order
+    #[arg(long, requires("sort"))]
+    reverse: bool,
+
+    /// Sort siblings while walking, or all results at the end (always global with --threads).
+    /// Archive members are sorted among themselves, after their archive, while walking
+    #[arg(long, value_name = "SCOPE", value_enum, default_value = "dir", requires("sort"))]
+    sort_scope: SortScope,
+
+    /// Print groups of regular files with identical contents
+    #[arg(long, conflicts_with_all(["output", "print_match", "sort", "ordered"]))]
+    duplicates: bool,
+
+    /// Replace duplicates with hard links to the first file of their group
+    #[arg(long, requires("duplicates"))]
+    hardlink: bool,
+
+    /// Delete all but the first file of each group of duplicates
+    #[arg(long, requires("duplicates"), conflicts_with("hardlink"))]
+    delete_dupes: bool,
+
+    /// Print a summary of the matches instead of listing them
+    #[arg(long, conflicts_with_all(["output", "print_match", "sort", "ordered", "duplicates"]))]
+    stats: bool,
+
+    /// After the walk, keep reporting matches as they are created, modified
+    /// or removed; every line starts with found, created, modified or removed
+    #[arg(long, conflicts_with_all(["output", "duplicates", "stats"]))]
+    watch: bool,
+
+    /// Also look inside .tar, .tar.gz, .tgz and .zip files, printing members
+    /// as ARCHIVE!/PATH
+    #[arg(long, conflicts_with_all(["output", "contains", "duplicates", "stats", "watch"]))]
+    search_archives: bool,
+
+    /// Don't print error messages; entries that can't be read still count
+    /// towards the summary, and every error towards the exit status
+    #[arg(long)]
+    quiet_errors: bool,
+
+    /// Follow symbolic links
+    #[arg(short('L'), long)]
+    follow: bool,
+
+    /// Follow symbolic links given as search paths only
+    #[arg(short('H'), long, conflicts_with("follow"))]
+    follow_args: bool,
+
+    /// Don't descend into directories on other file systems
+    #[arg(long)]
+    xdev: bool,
+
+    /// Only regular files whose contents match PATTERN
+    #[arg(long, value_name = "PATTERN", value_parser(Regex::new))]
+    contains: Option<Regex>,
+
+    /// Skip files larger than SIZE (e.g. 512k, 10M, 1G) in --contains
+    #[arg(long, value_name = "SIZE", value_parser(parse_size), requires("contains"))]
+    max_filesize: Option<u64>,
+
+    /// Print the first matching line as PATH:LINE:TEXT
+    #[arg(long, requires("contains"), conflicts_with("output"))]
+    print_match: bool,
+
+    /// List entries like `ls -dils`
+    #[arg(long, group("output"))]
+    ls: bool,
+
+    /// Print entries with find's -printf FORMAT (e.g. '%p %s %TY-%Tm-%Td\n')
+    #[arg(long, value_name = "FORMAT", value_parser(PrintfFormat::parse), group("output"))]
+    printf: Option<PrintfFormat>,
+
+    /// Print one JSON object per entry
+    #[arg(long, group("output"))]
+    json: bool,
+}
+
+/// `--type` value. The library's EntryType doesn't depend on clap, so the
+/// command line names are given here.
+#[derive(Debug, Clone, Copy)]
+struct TypeArg(EntryType);
+
+// implement ValueEnum trait
+// https://docs.rs/clap/latest/clap/trait.ValueEnum.html
+impl ValueEnum for TypeArg {
+    fn value_variants<'a>() -> &'a [Self] {
+        &[
+            TypeArg(EntryType::Dir),
+            TypeArg(EntryType::File),
+            TypeArg(EntryType::Link),
+            TypeArg(EntryType::Socket),
+            TypeArg(EntryType::Fifo),
+            TypeArg(EntryType::BlockDevice),
+            TypeArg(EntryType::CharDevice),
+            TypeArg(EntryType::BrokenLink),
+        ]
+    }
+    fn to_possible_value(&self) -> Option<PossibleValue> {
+        Some(match self.0 {
+            EntryType::Dir => PossibleValue::new("d"),
+            EntryType::File  => PossibleValue::new("f"),
+            EntryType::Link => PossibleValue::new("l"),
+            EntryType::Socket => PossibleValue::new("s").help("socket"),
+            EntryType::Fifo => PossibleValue::new("p").help("named pipe (FIFO)"),
+            EntryType::BlockDevice => PossibleValue::new("b").help("block device"),
+            EntryType::CharDevice => PossibleValue::new("c").help("character device"),
+            EntryType::BrokenLink => PossibleValue::new("broken").help("symbolic link to a missing target"),
+        })
+    }
 }
 
-fn is_type_matched(cur_entry: &walkdir::DirEntry, opt_type: &Vec<EntryType>) -> bool {
-    // DirEntry.file_type() -> std::fs::FileType
-    // https://doc.rust-lang.org/nightly/std/fs/struct.FileType.html
-    for t in opt_type {
-        match t {
-            EntryType::Dir => {
-                if cur_entry.file_type().is_dir() { return true; }
-            },
-            EntryType::File => {
-                if cur_entry.file_type().is_file() { return true; }
-            },
-            EntryType::Link => {
-                if cur_entry.file_type().is_symlink() { return true; }
-            },
+/// An entry that passed every filter.
+struct Found {
+    /// The entry, or the archive the member is in
+    entry: Entry,
+    /// The first matching line, with --contains
+    line: Option<LineMatch>,
+    /// With --search-archives
+    member: Option<Member>,
+}
+
+fn parse_ipath(s: &str) -> Result<Regex, regex::Error> {
+    RegexBuilder::new(s).case_insensitive(true).build()
+}
+
+/// Parses sizes such as `512`, `10k`, `10M` or `1G` (powers of 1024).
+fn parse_size(s: &str) -> Result<u64, String> {
+    let (num, unit) = match s.find(|c: char| !c.is_ascii_digit()) {
+        Some(i) => s.split_at(i),
+        None => (s, ""),
+    };
+    let shift = match unit.to_ascii_lowercase().as_str() {
+        "" => 0,
+        "k" => 10,
+        "m" => 20,
+        "g" => 30,
+        "t" => 40,
+        _ => return Err(format!("unknown size unit '{unit}'")),
+    };
+    let num: u64 = num.parse().map_err(|e| format!("{e}"))?;
+    num.checked_mul(1 << shift).ok_or_else(|| "size too large".to_string())
+}
+
+fn write_found(
+    out: &mut impl Write,
+    printer: &Printer,
+    event: Option<Event>,
+    found: &Found,
+) -> io::Result<()> {
+    if let Some(event) = event {
+        write!(out, "{} ", event.name())?;
+    }
+    if let Some(member) = &found.member {
+        return osstr::write_path(out, &member.path());
+    }
+    let path = found.entry.path();
+    match &found.line {
+        None => printer.write(out, &found.entry),
+        Some(m) => {
+            out.write_all(&osstr::to_bytes(path.as_os_str()))?;
+            write!(out, ":{}:", m.line_number)?;
+            out.write_all(&m.line)?;
+            out.write_all(b"\n")
         }
     }
-    false
 }
 
-fn run(args: Args) -> Result<()> {
-    let opt_names = args.names;
-    let opt_types = args.entry_types;
-
-    let type_filter = |entry: &walkdir::DirEntry| {
-        opt_types.is_empty()
-            || opt_types.iter().any(|entry_type| match entry_type {
-                EntryType::Dir => entry.file_type().is_dir(),
-                EntryType::File => entry.file_type().is_file(),
-                EntryType::Link => entry.file_type().is_symlink(),
-            })
+/// Builds the matcher for the filter options. Values given to one option
+/// are ORed and the options are ANDed. --path and --ipath count as one
+/// option.
+fn build_matcher(args: &mut Args) -> Result<And> {
+    fn any_of<T>(values: Vec<T>, matcher: impl Fn(T) -> Box<dyn Matcher>) -> Option<Box<dyn Matcher>> {
+        if values.is_empty() {
+            return None;
+        }
+        Some(Box::new(Or(values.into_iter().map(matcher).collect())))
+    }
+
+    let mut paths = take(&mut args.path_patterns);
+    paths.append(&mut args.ipath_patterns);
+    // 절대 경로는 시작할 때의 현재 디렉터리 기준
+    let cwd = if args.absolute_path { Some(std::env::current_dir()?) } else { None };
+    let filters = [
+        any_of(take(&mut args.entry_types), |t| Box::new(matcher::Type(t.0))),
+        any_of(take(&mut args.names), |re| Box::new(matcher::Name(re))),
+        any_of(paths, |regex| Box::new(matcher::Path { regex, cwd: cwd.clone() })),
+    ]
+    .into_iter()
+    .flatten()
+    .collect();
+    Ok(And(filters))
+}
+
+fn run(mut args: Args) -> Result<()> {
+    let started = Instant::now();
+    report::set_quiet(args.quiet_errors);
+    let matcher = build_matcher(&mut args)?;
+
+    let ignore_rules = args.gitignore.then(|| IgnoreRules::new(args.hidden));
+    let walk_options = WalkOptions {
+        follow: args.follow,
+        follow_args: args.follow_args,
+        xdev: args.xdev,
+    };
+
+    let walk_filter = |entry: &walkdir::DirEntry| {
+        !ignore_rules.as_ref().is_some_and(|rules| rules.is_excluded(entry))
     };
 
-    let name_filter = |entry: &walkdir::DirEntry| {
-        opt_names.is_empty()
-            || opt_names.iter().any(|re| {
-                re.is_match(&entry.file_name().to_string_lossy(),)
-            })
+    let content_matcher = args.contains.map(|regex| ContentMatcher {
+        regex,
+        max_filesize: args.max_filesize,
+    });
+    let print_match = args.print_match;
+
+    // 이름, 타입 필터를 통과한 파일만 열어서 내용을 검사 (비용이 큰 필터는 마지막에)
+    let content_filter = |entry: Entry| {
+        let Some(matcher) = &content_matcher else {
+            return Some(Found { entry, line: None, member: None });
+        };
+        match matcher.first_match(&entry) {
+            Err(e) => {
+                report::error(format_args!("{}: {e}", entry.path().display()));
+                None
+            }
+            Ok(None) => None,
+            Ok(Some(m)) => Some(Found {
+                entry,
+                line: print_match.then_some(m),
+                member: None,
+            }),
+        }
     };
 
-    for p in args.paths {
-        let entries = WalkDir::new(p)
+    let sorter = match (args.sort, args.ordered) {
+        (Some(key), _) => Some(Sorter { key, reverse: args.reverse }),
+        (None, true) => Some(Sorter { key: SortKey::Path, reverse: false }),
+        (None, false) => None,
+    };
+    // 병렬 탐색은 디렉토리 순서가 정해져 있지 않으므로 항상 전체 정렬
+    let sort_globally = sorter.is_some()
+        && (args.threads.is_some() || args.sort_scope == SortScope::Global);
+
+    let search_archives = args.search_archives;
+    let archive_members = |entry: &Entry| {
+        if !search_archives || !archive::is_archive(entry) {
+            return vec![];
+        }
+        // 손상된 압축 파일도 읽은 데까지는 검사
+        let mut members = vec![];
+        if let Err(e) = archive::members(entry, &mut members) {
+            report::error(format_args!("{}: {e}", entry.path().display()));
+        }
+        // 전체 정렬하지 않을 때는 압축 파일 바로 뒤에 안의 항목끼리 정렬
+        if let (Some(sorter), false) = (sorter, sort_globally) {
+            sorter.sort(&mut members, |m| Item::Member(m));
+        }
+        members
             .into_iter()
-            //.filter_map(|e| e.ok()) // -> Ok() 인것만 필터링해서 iterater 반환
-            .filter_map(|e| match e { // Result<> 타입을 Option<> 으로 변환필요
-                Err(e) => {
-                    eprintln!("{e}");
-                    None
+            .filter(|m| matcher.is_member_match(m))
+            .map(|m| Found { entry: entry.clone(), line: None, member: Some(m) })
+            .collect()
+    };
+
+    // 압축 파일 자체가 매칭되지 않아도 안의 항목은 검사
+    let select = |entry: Entry| {
+        let members = archive_members(&entry);
+        let found = matcher.is_match(&entry).then_some(entry).and_then(content_filter);
+        found.into_iter().chain(members)
+    };
+
+    let report_error = |e: Result<Entry>| match e {
+        // Result<> 타입을 Option<> 으로 변환필요
+        Err(e) => {
+            report::error(e);
+            None
+        }
+        Ok(e) => Some(e)
+    };
+
+    let printer = Printer::new(if args.ls {
+        OutputFormat::Ls
+    } else if let Some(format) = args.printf {
+        OutputFormat::Printf(format)
+    } else if args.json {
+        OutputFormat::Json
+    } else {
+        OutputFormat::Path
+    });
+
+    // 결과를 모았다가 한번에 출력하지 않고 찾는 즉시 출력 (전체 정렬, 중복 검사할 때만 모음)
+    let collect_all = sort_globally || args.duplicates;
+    let mut out = BufWriter::new(io::stdout().lock());
+    let mut collected = vec![];
+    let mut stats = args.stats.then(Stats::default);
+    // 감시를 먼저 시작해야 처음 탐색하는 동안 생긴 변경도 놓치지 않음
+    let watcher = match args.watch {
+        true => Some(Watcher::new(&args.paths, walk_options)?),
+        false => None,
+    };
+    let found_event = args.watch.then_some(Event::Found);
+    // 지금까지 출력한 경로. 삭제된 항목은 필터를 다시 적용할 수 없으므로 이걸로 판단
+    let mut matched = BTreeSet::new();
+    let mut emit = |found: Found| -> io::Result<()> {
+        if watcher.is_some() {
+            matched.insert(found.entry.path().to_path_buf());
+        }
+        if let Some(stats) = &mut stats {
+            stats.add(&found.entry);
+            Ok(())
+        } else if collect_all {
+            collected.push(found);
+            Ok(())
+        } else {
+            write_found(&mut out, &printer, found_event, &found)
+        }
+    };
+
+    if let Some(threads) = args.threads {
+        let paths = &args.paths;
+        let (tx, rx) = mpsc::channel();
+        thread::scope(|s| {
+            let walker = s.spawn(move || parallel::walk(paths, threads, walk_options, &walk_filter, tx));
+            let entries = rx
+                .into_iter()
+                .flatten()
+                .filter_map(report_error)
+                .flat_map(select);
+
+            for f in entries {
+                emit(f)?;
+            }
+            walker.join().unwrap()
+        })?;
+    } else {
+        for p in &args.paths {
+            let mut walkdir = walk_options.walkdir(p);
+            if let (Some(sorter), false) = (sorter, sort_globally) {
+                walkdir = walkdir.sort_by(move |a, b| sorter.compare(a, b));
+            }
+            let entries = walkdir
+                .into_iter()
+                .filter_entry(walk_filter) // 디렉토리가 제외되면 하위 항목도 탐색하지 않음
+                .map(|e| walk_options.check(e, 0))
+                //.filter_map(|e| e.ok()) // -> Ok() 인것만 필터링해서 iterater 반환
+                .filter_map(report_error)
+                .flat_map(select);
+
+            for f in entries {
+                emit(f)?;
+            }
+        }
+    }
+
+    if let Some(stats) = stats {
+        stats.write(&mut out, started.elapsed())?;
+    } else if args.duplicates {
+        let action = if args.hardlink {
+            duplicates::Action::Hardlink
+        } else if args.delete_dupes {
+            duplicates::Action::Delete
+        } else {
+            duplicates::Action::List
+        };
+        let entries = collected.into_iter().map(|f| f.entry.into_dir_entry()).collect();
+        return report_duplicates(&mut out, entries, action);
+    }
+
+    if let (Some(sorter), true) = (sorter, sort_globally) {
+        sorter.sort(&mut collected, |f| match &f.member {
+            Some(m) => Item::Member(m),
+            None => Item::Entry(&f.entry),
+        });
+        for f in &collected {
+            write_found(&mut out, &printer, found_event, f)?;
+        }
+    }
+    out.flush()?;
+
+    if let Some(watcher) = watcher {
+        let excluded = |path: &Path, is_dir: bool| {
+            ignore_rules.as_ref().is_some_and(|rules| rules.is_path_excluded(path, is_dir))
+        };
+        watcher.run(&excluded, |change| {
+            match change {
+                Change::Entry(event, entry) => {
+                    let path = entry.path().to_path_buf();
+                    // 새 디렉토리의 파일은 디렉토리 탐색과 자신의 이벤트 양쪽에서 올 수 있음
+                    if event == Event::Created && matched.contains(&path) {
+                        return Ok(());
+                    }
+                    match select(entry).next() {
+                        Some(found) => {
+                            write_found(&mut out, &printer, Some(event), &found)?;
+                            matched.insert(path);
+                        }
+                        // 수정되어 더 이상 매칭되지 않는 항목
+                        None => {
+                            matched.remove(&path);
+                        }
+                    }
+                }
+                Change::Removed(path) => {
+                    // 디렉토리가 옮겨지면 하위 항목의 이벤트는 오지 않으므로 함께 처리
+                    let removed: Vec<PathBuf> = matched
+                        .range(path.clone()..)
+                        .take_while(|p| p.starts_with(&path))
+                        .cloned()
+                        .collect();
+                    for p in removed {
+                        matched.remove(&p);
+                        write!(out, "{} ", Event::Removed.name())?;
+                        osstr::write_path(&mut out, &p)?;
+                    }
                 }
-                Ok(e) => Some(e)
-            })
-            .filter(type_filter) // filter 에 전달하는 closure는 bool 타입 리턴해야함.
-            .filter(name_filter)
-            .map(|entry| entry.path().display().to_string()) // vec(DirEntry)  타입을 vec(String) 타입으로 변환
-            .collect::<Vec<_>>();
-
-        println!("{}", entries.join("\n"));
+            }
+            out.flush()
+        })?;
+    }
+    Ok(())
+}
+
+/// Prints the groups of duplicates, then hard links or deletes them once
+/// the user agrees.
+fn report_duplicates(
+    out: &mut impl Write,
+    entries: Vec<walkdir::DirEntry>,
+    action: duplicates::Action,
+) -> Result<()> {
+    let groups = duplicates::find(entries);
+    duplicates::print(out, &groups)?;
+    out.flush()?;
+
+    let count: usize = groups.iter().map(|g| g.len() - 1).sum();
+    let (question, done) = match action {
+        duplicates::Action::List => return Ok(()),
+        _ if count == 0 => return Ok(()),
+        duplicates::Action::Hardlink => (
+            format!("Replace {count} duplicate files with hard links?"),
+            "replaced with hard links",
+        ),
+        duplicates::Action::Delete => (format!("Delete {count} duplicate files?"), "deleted"),
+    };
+    if duplicates::confirm(&question)? {
+        let n = duplicates::apply(action, &groups);
+        eprintln!("{n} files {done}");
     }
     Ok(())
 }
 
 fn main() {
     if let Err(e) = run(Args::parse()) {
+        // `findr | head` 처럼 읽는 쪽이 먼저 끝나면 조용히 종료
+        if e.downcast_ref::<io::Error>().is_some_and(|e| e.kind() == io::ErrorKind::BrokenPipe) {
+            return;
+        }
         eprintln!("{e}");
         std::process::exit(1);
     }
+    // find 처럼 오류가 하나라도 있으면 실패로 종료
+    let errors = report::error_count();
+    if errors > 0 {
+        let entries = if errors == 1 { "entry" } else { "entries" };
+        eprintln!("findr: {errors} inaccessible {entries}");
+    }
+    if report::failed() {
+        std::process::exit(1);
+    }
 }
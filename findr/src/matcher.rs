@@ -0,0 +1,306 @@
+use crate::archive::Member;
+use crate::osstr;
+use crate::walk::{self, Entry};
+use regex::bytes::Regex;
+use std::borrow::Cow;
+use std::cmp::Ordering;
+use std::env;
+use std::fs::Metadata;
+use std::io;
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Decides whether an entry is part of the result.
+///
+/// Matchers are combined with `And`, `Or` and `Not`; boxed matchers are
+/// matchers too, so a combination can be built up at run time.
+pub trait Matcher {
+    fn is_match(&self, entry: &Entry) -> bool;
+
+    /// The same for a member of an archive, from what the archive records.
+    /// Matchers that don't say how match no member.
+    fn is_member_match(&self, _member: &Member) -> bool {
+        false
+    }
+}
+
+impl<M: Matcher + ?Sized> Matcher for Box<M> {
+    fn is_match(&self, entry: &Entry) -> bool {
+        (**self).is_match(entry)
+    }
+
+    fn is_member_match(&self, member: &Member) -> bool {
+        (**self).is_member_match(member)
+    }
+}
+
+/// Matches every entry.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct All;
+
+impl Matcher for All {
+    fn is_match(&self, _entry: &Entry) -> bool {
+        true
+    }
+
+    fn is_member_match(&self, _member: &Member) -> bool {
+        true
+    }
+}
+
+/// Matches entries every matcher matches; an empty `And` matches everything.
+pub struct And(pub Vec<Box<dyn Matcher>>);
+
+impl Matcher for And {
+    fn is_match(&self, entry: &Entry) -> bool {
+        self.0.iter().all(|m| m.is_match(entry))
+    }
+
+    fn is_member_match(&self, member: &Member) -> bool {
+        self.0.iter().all(|m| m.is_member_match(member))
+    }
+}
+
+/// Matches entries any matcher matches; an empty `Or` matches nothing.
+pub struct Or(pub Vec<Box<dyn Matcher>>);
+
+impl Matcher for Or {
+    fn is_match(&self, entry: &Entry) -> bool {
+        self.0.iter().any(|m| m.is_match(entry))
+    }
+
+    fn is_member_match(&self, member: &Member) -> bool {
+        self.0.iter().any(|m| m.is_member_match(member))
+    }
+}
+
+/// Matches entries the inner matcher doesn't.
+pub struct Not(pub Box<dyn Matcher>);
+
+impl Matcher for Not {
+    fn is_match(&self, entry: &Entry) -> bool {
+        !self.0.is_match(entry)
+    }
+
+    fn is_member_match(&self, member: &Member) -> bool {
+        !self.0.is_member_match(member)
+    }
+}
+
+#[derive(Debug, Eq, PartialEq, Clone, Copy)]
+pub enum EntryType {
+    Dir,
+    File,
+    Link,
+    Socket,
+    Fifo,
+    BlockDevice,
+    CharDevice,
+    BrokenLink,
+}
+
+/// Matches entries of one type (`--type`).
+#[derive(Debug, Clone, Copy)]
+pub struct Type(pub EntryType);
+
+impl Matcher for Type {
+    fn is_match(&self, entry: &Entry) -> bool {
+        // DirEntry.file_type() -> std::fs::FileType
+        // https://doc.rust-lang.org/nightly/std/fs/struct.FileType.html
+        let ft = entry.file_type();
+        match self.0 {
+            EntryType::Dir => ft.is_dir(),
+            EntryType::File => ft.is_file(),
+            EntryType::Link => ft.is_symlink(),
+            EntryType::BrokenLink => walk::is_broken_link(entry),
+            #[cfg(unix)]
+            special => {
+                // 소켓, FIFO, 디바이스 파일은 유닉스에만 있음
+                use std::os::unix::fs::FileTypeExt;
+                match special {
+                    EntryType::Socket => ft.is_socket(),
+                    EntryType::Fifo => ft.is_fifo(),
+                    EntryType::BlockDevice => ft.is_block_device(),
+                    EntryType::CharDevice => ft.is_char_device(),
+                    _ => false,
+                }
+            }
+            #[cfg(not(unix))]
+            _ => false,
+        }
+    }
+
+    /// Archives don't record whether a link's target exists, so no member
+    /// is a broken link.
+    fn is_member_match(&self, member: &Member) -> bool {
+        member.entry_type == Some(self.0) && self.0 != EntryType::BrokenLink
+    }
+}
+
+/// Matches the file name against a regex (`--name`).
+#[derive(Debug, Clone)]
+pub struct Name(pub Regex);
+
+impl Matcher for Name {
+    fn is_match(&self, entry: &Entry) -> bool {
+        // 파일 이름을 문자열로 바꾸지 않고 바이트 그대로 매칭 (UTF-8이 아닌 이름도 가능)
+        self.0.is_match(&osstr::to_bytes(entry.file_name()))
+    }
+
+    fn is_member_match(&self, member: &Member) -> bool {
+        self.0.is_match(member.file_name())
+    }
+}
+
+/// Matches the path below the search path against a regex (`--path`), or
+/// the absolute path when `cwd` is set. The search path itself is matched
+/// as an empty path.
+#[derive(Debug, Clone)]
+pub struct Path {
+    pub regex: Regex,
+    /// The directory relative search paths are taken from.
+    pub cwd: Option<PathBuf>,
+}
+
+impl Path {
+    /// Matches the path below the search path.
+    pub fn new(regex: Regex) -> Path {
+        Path { regex, cwd: None }
+    }
+
+    /// Matches the absolute path, relative to the current directory.
+    pub fn absolute(regex: Regex) -> io::Result<Path> {
+        Ok(Path { regex, cwd: Some(env::current_dir()?) })
+    }
+}
+
+impl Matcher for Path {
+    fn is_match(&self, entry: &Entry) -> bool {
+        let path = match &self.cwd {
+            Some(cwd) => Cow::Owned(absolute(cwd, entry.path())),
+            None => Cow::Borrowed(entry.relative_path()),
+        };
+        self.regex.is_match(&osstr::to_bytes(path.as_os_str()))
+    }
+
+    fn is_member_match(&self, member: &Member) -> bool {
+        let path = match &self.cwd {
+            Some(cwd) => member.path_under(&absolute(cwd, member.archive())),
+            None => member.relative_path(),
+        };
+        self.regex.is_match(&osstr::to_bytes(path.as_os_str()))
+    }
+}
+
+/// `path` below `cwd`, made absolute the way `std::path::absolute` does,
+/// without looking up the current directory again.
+fn absolute(cwd: &std::path::Path, path: &std::path::Path) -> PathBuf {
+    let path = cwd.join(path);
+    // 절대 경로는 현재 디렉터리를 읽지 않으므로 실패하지 않음
+    std::path::absolute(&path).unwrap_or(path)
+}
+
+/// A comparison with a number, as find writes it: `+N` is more than N,
+/// `-N` less than N and `N` exactly N.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Compare {
+    pub ordering: Ordering,
+    pub value: u64,
+}
+
+impl Compare {
+    /// Parses `[+-]N`, with `parse_value` for N.
+    pub fn parse(s: &str, parse_value: impl Fn(&str) -> Result<u64, String>) -> Result<Compare, String> {
+        let (ordering, value) = match s.as_bytes().first() {
+            Some(b'+') => (Ordering::Greater, &s[1..]),
+            Some(b'-') => (Ordering::Less, &s[1..]),
+            _ => (Ordering::Equal, s),
+        };
+        Ok(Compare {
+            ordering,
+            value: parse_value(value)?,
+        })
+    }
+
+    pub fn matches(&self, n: u64) -> bool {
+        n.cmp(&self.value) == self.ordering
+    }
+}
+
+/// Matches the size in bytes. Entries whose metadata can't be
+/// read never match.
+#[derive(Debug, Clone, Copy)]
+pub struct Size(pub Compare);
+
+impl Matcher for Size {
+    fn is_match(&self, entry: &Entry) -> bool {
+        entry.metadata().is_ok_and(|md| self.0.matches(md.len()))
+    }
+
+    fn is_member_match(&self, member: &Member) -> bool {
+        self.0.matches(member.size)
+    }
+}
+
+/// Which of an entry's timestamps.
+#[derive(Debug, Clone, Copy)]
+pub enum TimeKind {
+    Access,
+    Change,
+    Modify,
+}
+
+/// One of the timestamps in `md`; the epoch if the platform lacks it.
+pub fn time_of(md: &Metadata, kind: TimeKind) -> SystemTime {
+    match kind {
+        TimeKind::Access => md.accessed().unwrap_or(UNIX_EPOCH),
+        TimeKind::Modify => md.modified().unwrap_or(UNIX_EPOCH),
+        TimeKind::Change => change_time(md),
+    }
+}
+
+#[cfg(unix)]
+fn change_time(md: &Metadata) -> SystemTime {
+    use std::os::unix::fs::MetadataExt;
+    use std::time::Duration;
+    UNIX_EPOCH + Duration::new(md.ctime().max(0) as u64, md.ctime_nsec() as u32)
+}
+
+#[cfg(not(unix))]
+fn change_time(md: &Metadata) -> SystemTime {
+    md.modified().unwrap_or(UNIX_EPOCH)
+}
+
+/// Matches how many whole days ago one of the timestamps is, counted from
+/// `now`.
+#[derive(Debug, Clone, Copy)]
+pub struct Time {
+    pub kind: TimeKind,
+    pub days: Compare,
+    pub now: SystemTime,
+}
+
+impl Matcher for Time {
+    fn is_match(&self, entry: &Entry) -> bool {
+        let Ok(md) = entry.metadata() else {
+            return false;
+        };
+        self.matches(time_of(&md, self.kind))
+    }
+
+    /// Archives only record the modification time.
+    fn is_member_match(&self, member: &Member) -> bool {
+        match (self.kind, member.mtime) {
+            (TimeKind::Modify, Some(mtime)) => self.matches(mtime),
+            _ => false,
+        }
+    }
+}
+
+impl Time {
+    fn matches(&self, time: SystemTime) -> bool {
+        // 미래 시각은 0일 전으로 취급
+        let age = self.now.duration_since(time).unwrap_or_default();
+        self.days.matches(age.as_secs() / (24 * 60 * 60))
+    }
+}
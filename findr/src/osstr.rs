@@ -0,0 +1,41 @@
+use std::borrow::Cow;
+use std::ffi::{OsStr, OsString};
+use std::io::{self, Write};
+use std::path::Path;
+
+/// The raw bytes of `s`.
+///
+/// On Unix these are the bytes the OS handed us, so names that aren't valid
+/// UTF-8 survive untouched. Elsewhere OS strings are (nearly) Unicode and
+/// are converted lossily.
+#[cfg(unix)]
+pub fn to_bytes(s: &OsStr) -> Cow<'_, [u8]> {
+    use std::os::unix::ffi::OsStrExt;
+    Cow::Borrowed(s.as_bytes())
+}
+
+#[cfg(not(unix))]
+pub fn to_bytes(s: &OsStr) -> Cow<'_, [u8]> {
+    match s.to_string_lossy() {
+        Cow::Borrowed(s) => Cow::Borrowed(s.as_bytes()),
+        Cow::Owned(s) => Cow::Owned(s.into_bytes()),
+    }
+}
+
+/// The inverse of `to_bytes`.
+#[cfg(unix)]
+pub fn from_bytes(bytes: Vec<u8>) -> OsString {
+    use std::os::unix::ffi::OsStringExt;
+    OsString::from_vec(bytes)
+}
+
+#[cfg(not(unix))]
+pub fn from_bytes(bytes: Vec<u8>) -> OsString {
+    String::from_utf8_lossy(&bytes).into_owned().into()
+}
+
+/// Writes `path` followed by a newline, byte for byte.
+pub fn write_path(out: &mut impl Write, path: &Path) -> io::Result<()> {
+    out.write_all(&to_bytes(path.as_os_str()))?;
+    out.write_all(b"\n")
+}
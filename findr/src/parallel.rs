@@ -0,0 +1,166 @@
+use anyhow::Result;
+use findr::walk::{self, Entry, WalkOptions};
+use rayon::{Scope, ThreadPoolBuilder};
+use std::fs;
+use std::path::PathBuf;
+use std::sync::mpsc::Sender;
+use std::sync::Arc;
+use walkdir::DirEntry;
+
+/// Shared state of one parallel walk.
+struct Walker<'a> {
+    options: WalkOptions,
+    filter_entry: &'a (dyn Fn(&DirEntry) -> bool + Sync),
+    tx: Sender<Vec<Result<Entry>>>,
+}
+
+/// A directory to be read, and what is needed to check it against its root.
+struct Dir {
+    path: PathBuf,
+    depth: usize,
+    /// Device of the search root, for `--xdev`.
+    root_device: Option<u64>,
+    /// Canonical paths from here up to the root, for loop detection when
+    /// following links.
+    ancestors: Option<Arc<Ancestor>>,
+}
+
+struct Ancestor {
+    path: PathBuf,
+    canonical: PathBuf,
+    parent: Option<Arc<Ancestor>>,
+}
+
+/// Walks `paths` on a work-stealing pool of `threads` threads.
+///
+/// Every directory is listed by its own task, so idle threads steal whole
+/// subtrees from busy ones. Entries are sent to `tx` one batch per directory,
+/// in no particular order.
+/// Like `WalkDir::filter_entry`, entries rejected by `filter_entry` are not
+/// sent and directories rejected by it are not descended into.
+pub fn walk(
+    paths: &[PathBuf],
+    threads: usize,
+    options: WalkOptions,
+    filter_entry: &(dyn Fn(&DirEntry) -> bool + Sync),
+    tx: Sender<Vec<Result<Entry>>>,
+) -> Result<()> {
+    let pool = ThreadPoolBuilder::new().num_threads(threads).build()?;
+    let walker = Walker {
+        options,
+        filter_entry,
+        tx,
+    };
+    pool.scope(|s| {
+        for p in paths {
+            // max_depth(0) yields the root itself as a depth 0 entry
+            let batch = options
+                .walkdir(p)
+                .max_depth(0)
+                .into_iter()
+                .filter_map(|root| walker.visit(s, None, root))
+                .collect();
+            let _ = walker.tx.send(batch);
+        }
+    });
+    Ok(())
+}
+
+impl<'a> Walker<'a> {
+    /// Schedules `entry` to be read if it is a directory to descend into,
+    /// and returns what should be sent for it.
+    fn visit<'s>(
+        &'s self,
+        s: &Scope<'s>,
+        parent: Option<&Dir>,
+        entry: walkdir::Result<DirEntry>,
+    ) -> Option<Result<Entry>>
+    where
+        'a: 's,
+    {
+        let base_depth = parent.map_or(0, |p| p.depth);
+        let entry = match self.options.check(entry, base_depth) {
+            Err(e) => return Some(Err(e)),
+            Ok(e) => e,
+        };
+        if !(self.filter_entry)(&entry) {
+            return None;
+        }
+        if entry.file_type().is_dir() {
+            match self.descend(parent, &entry) {
+                Err(e) => return Some(Err(e)),
+                Ok(Some(dir)) => s.spawn(move |s| self.read_dir(s, dir)),
+                Ok(None) => {}
+            }
+        }
+        Some(Ok(entry))
+    }
+
+    /// The directory to read for `entry`, or `None` if it must not be
+    /// descended into.
+    fn descend(&self, parent: Option<&Dir>, entry: &Entry) -> Result<Option<Dir>> {
+        let root_device = match parent {
+            Some(parent) => parent.root_device,
+            None if self.options.xdev => Some(device(entry)?),
+            None => None,
+        };
+        if parent.is_some() && root_device.is_some_and(|d| device(entry).ok() != Some(d)) {
+            return Ok(None);
+        }
+
+        let mut ancestors = None;
+        if self.options.follow {
+            let canonical = fs::canonicalize(entry.path())?;
+            let parent_ancestors = parent.and_then(|p| p.ancestors.clone());
+            let mut next = parent_ancestors.as_deref();
+            while let Some(a) = next {
+                if a.canonical == canonical {
+                    return Err(walk::loop_error(entry.path(), &a.path));
+                }
+                next = a.parent.as_deref();
+            }
+            ancestors = Some(Arc::new(Ancestor {
+                path: entry.path().to_path_buf(),
+                canonical,
+                parent: parent_ancestors,
+            }));
+        }
+
+        Ok(Some(Dir {
+            path: entry.path().to_path_buf(),
+            depth: entry.depth(),
+            root_device,
+            ancestors,
+        }))
+    }
+
+    fn read_dir<'s>(&'s self, s: &Scope<'s>, dir: Dir)
+    where
+        'a: 's,
+    {
+        // A one level WalkDir lists the directory as walkdir::DirEntry,
+        // so the usual filters work on the entries unchanged.
+        let batch = self
+            .options
+            .walkdir(&dir.path)
+            .follow_root_links(true)
+            .min_depth(1)
+            .max_depth(1)
+            .into_iter()
+            .filter_map(|child| self.visit(s, Some(&dir), child))
+            .collect();
+        let _ = self.tx.send(batch);
+    }
+}
+
+#[cfg(unix)]
+fn device(entry: &DirEntry) -> Result<u64> {
+    use std::os::unix::fs::MetadataExt;
+    Ok(entry.metadata()?.dev())
+}
+
+#[cfg(not(unix))]
+fn device(_entry: &DirEntry) -> Result<u64> {
+    // no portable device number; --xdev is only honored by the sequential walk
+    Ok(0)
+}
@@ -0,0 +1,37 @@
+use std::fmt::Display;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+
+// 오류는 여러 모듈과 스레드에서 생기므로 프로세스 전체에서 하나로 셈
+static QUIET: AtomicBool = AtomicBool::new(false);
+static ERRORS: AtomicU64 = AtomicU64::new(0);
+static FAILED: AtomicBool = AtomicBool::new(false);
+
+/// Stops printing error messages (`--quiet-errors`); they are still counted.
+pub fn set_quiet(quiet: bool) {
+    QUIET.store(quiet, Ordering::Relaxed);
+}
+
+/// Reports an entry that couldn't be read, walked or searched.
+pub fn error(e: impl Display) {
+    ERRORS.fetch_add(1, Ordering::Relaxed);
+    other_error(e);
+}
+
+/// Reports an error that isn't about an entry, such as a bad ignore
+/// pattern. It fails the run but isn't counted as an inaccessible entry.
+pub fn other_error(e: impl Display) {
+    FAILED.store(true, Ordering::Relaxed);
+    if !QUIET.load(Ordering::Relaxed) {
+        eprintln!("findr: {e}");
+    }
+}
+
+/// How many entries couldn't be read so far.
+pub fn error_count() -> u64 {
+    ERRORS.load(Ordering::Relaxed)
+}
+
+/// Whether any error was reported so far.
+pub fn failed() -> bool {
+    FAILED.load(Ordering::Relaxed)
+}
@@ -0,0 +1,108 @@
+use clap::ValueEnum;
+use findr::archive::Member;
+use findr::osstr;
+use std::cmp::Ordering;
+use std::ffi::OsString;
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+use walkdir::DirEntry;
+
+/// What entries are sorted by (`--sort`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum SortKey {
+    /// File name
+    Name,
+    /// Whole path
+    Path,
+    /// Size in bytes, smallest first
+    Size,
+    /// Modification time, oldest first
+    Mtime,
+}
+
+/// Where the order applies (`--sort-scope`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum SortScope {
+    /// Siblings are sorted while walking, so output streams as usual
+    Dir,
+    /// All results are collected and sorted before printing
+    Global,
+}
+
+/// An entry's sort key. Ties are broken by path so the order is total.
+#[derive(PartialEq, Eq, PartialOrd, Ord)]
+enum SortValue {
+    Name(OsString, PathBuf),
+    Path(PathBuf),
+    Size(u64, PathBuf),
+    Mtime(SystemTime, PathBuf),
+}
+
+/// What is sorted: a walked entry or a member of an archive, which is
+/// sorted by what the archive records about it.
+#[derive(Clone, Copy)]
+pub enum Item<'a> {
+    Entry(&'a DirEntry),
+    Member(&'a Member),
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct Sorter {
+    pub key: SortKey,
+    pub reverse: bool,
+}
+
+impl Sorter {
+    /// Compares two entries; for `WalkDir::sort_by`.
+    pub fn compare(&self, a: &DirEntry, b: &DirEntry) -> Ordering {
+        let ord = self.value(Item::Entry(a)).cmp(&self.value(Item::Entry(b)));
+        if self.reverse {
+            ord.reverse()
+        } else {
+            ord
+        }
+    }
+
+    /// Sorts `items` by the entry or member `item_of` returns for each,
+    /// reading every entry's metadata once.
+    pub fn sort<T>(&self, items: &mut [T], item_of: impl Fn(&T) -> Item<'_>) {
+        items.sort_by_cached_key(|item| self.value(item_of(item)));
+        if self.reverse {
+            items.reverse();
+        }
+    }
+
+    fn value(&self, item: Item) -> SortValue {
+        let entry = match item {
+            Item::Entry(entry) => entry,
+            Item::Member(member) => return self.member_value(member),
+        };
+        let path = entry.path().to_path_buf();
+        match self.key {
+            SortKey::Name => SortValue::Name(entry.file_name().to_os_string(), path),
+            SortKey::Path => SortValue::Path(path),
+            SortKey::Size => {
+                let size = entry.metadata().map_or(0, |m| m.len());
+                SortValue::Size(size, path)
+            }
+            SortKey::Mtime => {
+                let mtime = entry
+                    .metadata()
+                    .ok()
+                    .and_then(|m| m.modified().ok())
+                    .unwrap_or(UNIX_EPOCH);
+                SortValue::Mtime(mtime, path)
+            }
+        }
+    }
+
+    fn member_value(&self, member: &Member) -> SortValue {
+        let path = member.path();
+        match self.key {
+            SortKey::Name => SortValue::Name(osstr::from_bytes(member.file_name().to_vec()), path),
+            SortKey::Path => SortValue::Path(path),
+            SortKey::Size => SortValue::Size(member.size, path),
+            SortKey::Mtime => SortValue::Mtime(member.mtime.unwrap_or(UNIX_EPOCH), path),
+        }
+    }
+}
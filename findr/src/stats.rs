@@ -0,0 +1,123 @@
+use crate::format;
+use crate::report;
+use findr::osstr;
+use findr::walk::{self, Entry};
+use std::collections::HashMap;
+use std::ffi::OsString;
+use std::io::{self, Write};
+use std::path::PathBuf;
+use std::time::Duration;
+
+/// Order the type counts are printed in, the same as `--type`.
+const TYPES: [&str; 8] = [
+    "directory",
+    "file",
+    "symlink",
+    "broken_link",
+    "socket",
+    "fifo",
+    "block_device",
+    "char_device",
+];
+
+/// Files and bytes for one extension.
+#[derive(Debug, Default)]
+struct ExtStats {
+    files: u64,
+    bytes: u64,
+}
+
+/// Summary of the matched entries (`--stats`).
+#[derive(Debug, Default)]
+pub struct Stats {
+    entries: u64,
+    by_type: HashMap<&'static str, u64>,
+    total_size: u64,
+    /// Ties go to the smaller path so the result doesn't depend on the
+    /// walk order.
+    largest: Option<(u64, PathBuf)>,
+    deepest: Option<(usize, PathBuf)>,
+    /// `None` for files without an extension.
+    by_extension: HashMap<Option<OsString>, ExtStats>,
+}
+
+impl Stats {
+    pub fn add(&mut self, entry: &Entry) {
+        self.entries += 1;
+        let kind = if walk::is_broken_link(entry) {
+            "broken_link"
+        } else {
+            format::type_name(entry.file_type())
+        };
+        *self.by_type.entry(kind).or_default() += 1;
+
+        let path = entry.path();
+        let deeper = match &self.deepest {
+            None => true,
+            Some((depth, p)) => entry.depth() > *depth || (entry.depth() == *depth && path < p.as_path()),
+        };
+        if deeper {
+            self.deepest = Some((entry.depth(), path.to_path_buf()));
+        }
+
+        if !entry.file_type().is_file() {
+            return;
+        }
+        let size = match entry.metadata() {
+            Ok(md) => md.len(),
+            Err(e) => {
+                report::error(e);
+                return;
+            }
+        };
+        self.total_size += size;
+        let larger = match &self.largest {
+            None => true,
+            Some((largest, p)) => size > *largest || (size == *largest && path < p.as_path()),
+        };
+        if larger {
+            self.largest = Some((size, path.to_path_buf()));
+        }
+        let ext = self.by_extension.entry(path.extension().map(|e| e.to_os_string())).or_default();
+        ext.files += 1;
+        ext.bytes += size;
+    }
+
+    /// Prints the summary. Extensions are listed by file count, most first.
+    pub fn write(&self, out: &mut impl Write, elapsed: Duration) -> io::Result<()> {
+        writeln!(out, "entries: {}", self.entries)?;
+        for kind in TYPES {
+            if let Some(n) = self.by_type.get(kind) {
+                writeln!(out, "  {kind}: {n}")?;
+            }
+        }
+        writeln!(out, "total size: {}", self.total_size)?;
+        if let Some((size, path)) = &self.largest {
+            write!(out, "largest file: {size} ")?;
+            osstr::write_path(out, path)?;
+        }
+        if let Some((depth, path)) = &self.deepest {
+            write!(out, "deepest path: {depth} ")?;
+            osstr::write_path(out, path)?;
+        }
+        writeln!(out, "elapsed: {:.3}s", elapsed.as_secs_f64())?;
+
+        if self.by_extension.is_empty() {
+            return Ok(());
+        }
+        let mut exts: Vec<_> = self.by_extension.iter().collect();
+        exts.sort_by(|(a, x), (b, y)| y.files.cmp(&x.files).then_with(|| a.cmp(b)));
+        writeln!(out, "extensions:")?;
+        for (ext, s) in exts {
+            match ext {
+                Some(ext) => {
+                    out.write_all(b"  .")?;
+                    out.write_all(&osstr::to_bytes(ext))?;
+                }
+                None => out.write_all(b"  (none)")?,
+            }
+            writeln!(out, ": {} files, {} bytes", s.files, s.bytes)?;
+        }
+        Ok(())
+    }
+}
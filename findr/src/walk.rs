@@ -0,0 +1,141 @@
+use anyhow::{anyhow, Result};
+use std::fs;
+use std::ops::Deref;
+use std::path::Path;
+use walkdir::{DirEntry, WalkDir};
+
+/// A walked entry and its depth below the search path it was found under.
+///
+/// The parallel walker lists every directory with a WalkDir of its own, so
+/// `DirEntry::depth` is only relative to that directory; `Entry::depth` is
+/// always relative to the search path.
+#[derive(Debug, Clone)]
+pub struct Entry {
+    entry: DirEntry,
+    depth: usize,
+}
+
+impl Entry {
+    pub fn depth(&self) -> usize {
+        self.depth
+    }
+
+    /// The search path this entry was found under.
+    pub fn starting_point(&self) -> &Path {
+        let mut root = self.path();
+        for _ in 0..self.depth {
+            root = root.parent().unwrap_or(root);
+        }
+        root
+    }
+
+    /// The path below the search path; empty for the search path itself.
+    pub fn relative_path(&self) -> &Path {
+        self.path().strip_prefix(self.starting_point()).unwrap_or(self.path())
+    }
+
+    pub fn into_dir_entry(self) -> DirEntry {
+        self.entry
+    }
+}
+
+impl Deref for Entry {
+    type Target = DirEntry;
+
+    fn deref(&self) -> &DirEntry {
+        &self.entry
+    }
+}
+
+/// How symbolic links and mount points are treated while walking.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct WalkOptions {
+    /// Follow every symbolic link (`find -L`).
+    pub follow: bool,
+    /// Follow symbolic links given as search paths only (`find -H`).
+    pub follow_args: bool,
+    /// Do not descend into directories on other file systems (`find -xdev`).
+    pub xdev: bool,
+}
+
+impl WalkOptions {
+    /// A `WalkDir` over `path` configured with these options.
+    pub fn walkdir<P: AsRef<Path>>(&self, path: P) -> WalkDir {
+        WalkDir::new(path)
+            .follow_links(self.follow)
+            .follow_root_links(self.follow || self.follow_args)
+            .same_file_system(self.xdev)
+    }
+
+    /// Turns a walk error into the error reported to the user.
+    ///
+    /// When following links, a dangling link can't be followed and walkdir
+    /// reports it as an error. Like `find -L`, it is yielded as the link
+    /// itself instead.
+    ///
+    /// walkdir descends into a followed root link but still reports it as a
+    /// link, so with `--follow-args` the root is replaced by its target.
+    ///
+    /// `base_depth` is the depth of the directory the WalkDir started from.
+    pub fn check(&self, result: walkdir::Result<DirEntry>, base_depth: usize) -> Result<Entry> {
+        let e = match result {
+            Ok(entry) if self.follow_args && entry.depth() == 0 && entry.file_type().is_symlink() => {
+                let entry = followed(entry.path()).unwrap_or(entry);
+                return Ok(Entry { entry, depth: base_depth });
+            }
+            Ok(entry) => {
+                let depth = base_depth + entry.depth();
+                return Ok(Entry { entry, depth });
+            }
+            Err(e) => e,
+        };
+        if let Some(ancestor) = e.loop_ancestor() {
+            let path = e.path().unwrap_or(ancestor);
+            return Err(loop_error(path, ancestor));
+        }
+        if self.follow || self.follow_args {
+            if let Some(entry) = e.path().and_then(broken_link) {
+                let depth = base_depth + e.depth();
+                return Ok(Entry { entry, depth });
+            }
+        }
+        Err(e.into())
+    }
+}
+
+/// The error for a directory that leads back to one of its ancestors.
+pub fn loop_error(path: &Path, ancestor: &Path) -> anyhow::Error {
+    anyhow!(
+        "File system loop detected; '{}' is part of the same file system loop as '{}'",
+        path.display(),
+        ancestor.display()
+    )
+}
+
+/// Whether `entry` is a symbolic link whose target does not exist.
+pub fn is_broken_link(entry: &DirEntry) -> bool {
+    entry.path_is_symlink() && fs::metadata(entry.path()).is_err()
+}
+
+fn followed(path: &Path) -> Option<DirEntry> {
+    WalkDir::new(path)
+        .follow_links(true)
+        .max_depth(0)
+        .into_iter()
+        .next()?
+        .ok()
+}
+
+fn broken_link(path: &Path) -> Option<DirEntry> {
+    let is_link = fs::symlink_metadata(path).is_ok_and(|m| m.file_type().is_symlink());
+    if !is_link || fs::metadata(path).is_ok() {
+        return None;
+    }
+    // max_depth(0) without following yields the link itself
+    WalkDir::new(path)
+        .follow_root_links(false)
+        .max_depth(0)
+        .into_iter()
+        .next()?
+        .ok()
+}
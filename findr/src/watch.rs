@@ -0,0 +1,207 @@
+use crate::report;
+use anyhow::Result;
+use findr::walk::{Entry, WalkOptions};
+use notify::event::{EventKind, ModifyKind, RenameMode};
+use notify::{RecommendedWatcher, RecursiveMode, Watcher as _};
+use std::cmp::Reverse;
+use std::env;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Receiver};
+
+/// What happened to an entry; printed in front of it with `--watch`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Event {
+    /// Found by the initial walk
+    Found,
+    Created,
+    Modified,
+    Removed,
+}
+
+impl Event {
+    pub fn name(self) -> &'static str {
+        match self {
+            Event::Found => "found",
+            Event::Created => "created",
+            Event::Modified => "modified",
+            Event::Removed => "removed",
+        }
+    }
+}
+
+/// A change below one of the search paths.
+pub enum Change {
+    /// An entry that was created or modified.
+    Entry(Event, Entry),
+    /// A path that no longer exists. It can't be checked against the
+    /// filters any more, so it's up to the caller whether it matched.
+    Removed(PathBuf),
+}
+
+/// Watches the search paths for changes (`--watch`).
+///
+/// Uses inotify on Linux and the native API elsewhere. The watches are set
+/// up when the `Watcher` is created, so it should be created before the
+/// initial walk to not miss anything in between.
+pub struct Watcher {
+    /// The search paths as given, and as absolute paths the way the
+    /// events report them.
+    roots: Vec<(PathBuf, PathBuf)>,
+    options: WalkOptions,
+    _watcher: RecommendedWatcher,
+    rx: Receiver<notify::Result<notify::Event>>,
+}
+
+impl Watcher {
+    pub fn new(paths: &[PathBuf], options: WalkOptions) -> Result<Watcher> {
+        let (tx, rx) = mpsc::channel();
+        let mut watcher = notify::recommended_watcher(tx)?;
+        for p in paths {
+            watcher.watch(p, RecursiveMode::Recursive)?;
+        }
+        let cwd = env::current_dir()?;
+        let mut roots: Vec<_> = paths.iter().map(|p| (p.clone(), cwd.join(p))).collect();
+        // the deepest root a path is under decides its depth
+        roots.sort_by_key(|(_, abs)| Reverse(abs.components().count()));
+        Ok(Watcher {
+            roots,
+            options,
+            _watcher: watcher,
+            rx,
+        })
+    }
+
+    /// Calls `handle` for every change, until `handle` fails.
+    ///
+    /// `excluded` tells whether a path (and everything below it) is left out
+    /// of the search, like the walk filter; search roots are never passed
+    /// to it. A new directory is walked, so entries moved in with it are
+    /// reported as created too.
+    pub fn run(
+        &self,
+        excluded: &dyn Fn(&Path, bool) -> bool,
+        mut handle: impl FnMut(Change) -> io::Result<()>,
+    ) -> Result<()> {
+        for event in &self.rx {
+            let event = match event {
+                Ok(event) => event,
+                Err(e) => {
+                    report::error(e);
+                    continue;
+                }
+            };
+            for change in self.changes(event, excluded) {
+                handle(change)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn changes(&self, event: notify::Event, excluded: &dyn Fn(&Path, bool) -> bool) -> Vec<Change> {
+        let mut changes = vec![];
+        let mut paths = event.paths.into_iter().filter_map(|p| self.local_path(&p));
+        match event.kind {
+            EventKind::Create(_) | EventKind::Modify(ModifyKind::Name(RenameMode::To)) => {
+                for path in paths {
+                    self.created(path, excluded, &mut changes);
+                }
+            }
+            EventKind::Remove(_) | EventKind::Modify(ModifyKind::Name(RenameMode::From)) => {
+                changes.extend(paths.map(Change::Removed));
+            }
+            EventKind::Modify(ModifyKind::Name(RenameMode::Both)) => {
+                if let (Some(from), Some(to)) = (paths.next(), paths.next()) {
+                    changes.push(Change::Removed(from));
+                    self.created(to, excluded, &mut changes);
+                }
+            }
+            // a rename whose other half is unknown
+            EventKind::Modify(ModifyKind::Name(_)) => {
+                for path in paths {
+                    if path.symlink_metadata().is_ok() {
+                        self.created(path, excluded, &mut changes);
+                    } else {
+                        changes.push(Change::Removed(path));
+                    }
+                }
+            }
+            EventKind::Modify(_) => {
+                for path in paths {
+                    if let Some(entry) = self.entry(&path, excluded) {
+                        changes.push(Change::Entry(Event::Modified, entry));
+                    }
+                }
+            }
+            _ => {}
+        }
+        changes
+    }
+
+    /// Adds `path`, and everything below it if it's a directory.
+    fn created(&self, path: PathBuf, excluded: &dyn Fn(&Path, bool) -> bool, changes: &mut Vec<Change>) {
+        let Some(entry) = self.entry(&path, excluded) else {
+            return;
+        };
+        let depth = entry.depth();
+        let is_dir = entry.file_type().is_dir();
+        changes.push(Change::Entry(Event::Created, entry));
+        if !is_dir {
+            return;
+        }
+        let children = self
+            .options
+            .walkdir(&path)
+            .min_depth(1)
+            .into_iter()
+            .filter_entry(|e| !excluded(e.path(), e.file_type().is_dir()));
+        for child in children {
+            match self.options.check(child, depth) {
+                Ok(entry) => changes.push(Change::Entry(Event::Created, entry)),
+                Err(e) => report::error(e),
+            }
+        }
+    }
+
+    /// `path` from an event, below the search path it is under as given on
+    /// the command line, so it reads like the walk's output.
+    fn local_path(&self, path: &Path) -> Option<PathBuf> {
+        self.roots.iter().find_map(|(root, abs)| {
+            let rel = path.strip_prefix(abs).ok()?;
+            Some(match rel.as_os_str().is_empty() {
+                true => root.clone(),
+                false => root.join(rel),
+            })
+        })
+    }
+
+    /// The entry at `path`, unless it is gone already or excluded.
+    fn entry(&self, path: &Path, excluded: &dyn Fn(&Path, bool) -> bool) -> Option<Entry> {
+        let (root, _) = self.roots.iter().find(|(root, _)| path.starts_with(root))?;
+        let depth = path.strip_prefix(root).ok()?.components().count();
+        // -H only follows the search paths themselves
+        let options = match depth {
+            0 => self.options,
+            _ => WalkOptions { follow_args: false, ..self.options },
+        };
+        let result = options.walkdir(path).max_depth(0).into_iter().next()?;
+        let entry = match options.check(result, depth) {
+            Ok(entry) => entry,
+            Err(e) => {
+                if !path.symlink_metadata().is_err_and(|e| e.kind() == io::ErrorKind::NotFound) {
+                    report::error(e);
+                }
+                return None;
+            }
+        };
+        // below the root, the entry and every directory above it must pass
+        let mut below_root = entry.path().ancestors().take(depth);
+        if below_root.next().is_some() && excluded(entry.path(), entry.file_type().is_dir()) {
+            return None;
+        }
+        if below_root.any(|dir| excluded(dir, true)) {
+            return None;
+        }
+        Some(entry)
+    }
+}
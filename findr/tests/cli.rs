@@ -3,7 +3,8 @@ use assert_cmd::Command;
 use predicates::prelude::*;
 use pretty_assertions::assert_eq;
 use rand::{distributions::Alphanumeric, Rng};
-use std::{borrow::Cow, fs, path::Path};
+use std::{borrow::Cow, fs, path::Path, process::Output};
+use tempfile::TempDir;
 
 const PRG: &str = "findr";
 
@@ -28,10 +29,26 @@ fn skips_bad_dir() -> Result<()> {
     let bad = gen_bad_file();
     let expected = format!("{}: .* [(]os error [23][)]", &bad);
     Command::cargo_bin(PRG)?
-        .arg(&bad)
+        .args([&bad, "tests/inputs/a/b", "--sort", "path"])
         .assert()
-        .success()
-        .stderr(predicate::str::is_match(expected)?);
+        .failure()
+        .code(1)
+        .stdout("tests/inputs/a/b\ntests/inputs/a/b/b.csv\ntests/inputs/a/b/c\ntests/inputs/a/b/c/c.mp3\n")
+        .stderr(predicate::str::is_match(expected)?)
+        .stderr(predicate::str::ends_with("findr: 1 inaccessible entry\n"));
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn quiet_errors() -> Result<()> {
+    let bad = gen_bad_file();
+    Command::cargo_bin(PRG)?
+        .args([&bad, &gen_bad_file(), "--quiet-errors"])
+        .assert()
+        .failure()
+        .code(1)
+        .stderr("findr: 2 inaccessible entries\n");
     Ok(())
 }
 
@@ -60,14 +77,14 @@ fn dies_bad_type() -> Result<()> {
 
 // --------------------------------------------------
 #[cfg(windows)]
-fn format_file_name(expected_file: &str) -> Cow<str> {
+fn format_file_name(expected_file: &str) -> Cow<'_, str> {
     // Equivalent to: Cow::Owned(format!("{}.windows", expected_file))
     format!("{}.windows", expected_file).into()
 }
 
 // --------------------------------------------------
 #[cfg(not(windows))]
-fn format_file_name(expected_file: &str) -> Cow<str> {
+fn format_file_name(expected_file: &str) -> Cow<'_, str> {
     // Equivalent to: Cow::Borrowed(expected_file)
     expected_file.into()
 }
@@ -92,6 +109,64 @@ fn run(args: &[&str], expected_file: &str) -> Result<()> {
     Ok(())
 }
 
+// --------------------------------------------------
+// Runs findr in `dir`; it must succeed
+fn run_in(dir: &Path, args: &[&str]) -> Result<Output> {
+    let cmd = Command::cargo_bin(PRG)?
+        .current_dir(dir)
+        // keep the user's global excludes file out of the test
+        .env("HOME", dir)
+        .env("XDG_CONFIG_HOME", dir)
+        .env("TZ", "UTC")
+        .args(args)
+        .assert()
+        .success();
+    Ok(cmd.get_output().clone())
+}
+
+// --------------------------------------------------
+fn stdout(out: &Output) -> String {
+    String::from_utf8_lossy(&out.stdout).into_owned()
+}
+
+// --------------------------------------------------
+fn expected(expected_file: &str) -> Result<String> {
+    Ok(fs::read_to_string(format_file_name(expected_file).as_ref())?)
+}
+
+// --------------------------------------------------
+// Like run(), the lines may come in any order
+fn assert_lines(out: &Output, expected: &[&str]) {
+    let expected: Vec<&[u8]> = expected.iter().map(|s| s.as_bytes()).collect();
+    assert_byte_lines(out, &expected);
+}
+
+// --------------------------------------------------
+// Also for names that aren't valid UTF-8, shown escaped when they differ
+fn assert_byte_lines(out: &Output, expected: &[&[u8]]) {
+    let sorted = |lines: &mut dyn Iterator<Item = &[u8]>| {
+        let mut lines: Vec<String> = lines.map(|l| l.escape_ascii().to_string()).collect();
+        lines.sort();
+        lines
+    };
+    let mut lines = out.stdout.split(|b| *b == b'\n').filter(|l| !l.is_empty());
+    assert_eq!(sorted(&mut lines), sorted(&mut expected.iter().copied()));
+}
+
+// --------------------------------------------------
+// The output order must match exactly
+fn assert_stdout(out: &Output, expected: &str) {
+    assert_eq!(stdout(out), expected);
+}
+
+// --------------------------------------------------
+fn assert_stats(out: &Output, expected: &str) {
+    // 소요 시간은 실행할 때마다 달라지므로 비교에서 제외
+    let stdout = stdout(out);
+    let lines: Vec<&str> = stdout.lines().filter(|l| !l.starts_with("elapsed: ")).collect();
+    assert_eq!(lines.join("\n"), expected);
+}
+
 // --------------------------------------------------
 #[test]
 fn path1() -> Result<()> {
@@ -281,6 +356,996 @@ fn path_g() -> Result<()> {
     run(&["tests/inputs/g.csv"], "tests/expected/path_g.txt")
 }
 
+// --------------------------------------------------
+#[test]
+fn no_match_prints_nothing() -> Result<()> {
+    for args in [
+        &["tests/inputs", "-n", "nothing-matches-this"][..],
+        &["tests/inputs", "-n", "nothing-matches-this", "-j", "2"],
+        &["tests/inputs", "-n", "nothing-matches-this", "-j", "2", "--ordered"],
+    ] {
+        Command::cargo_bin(PRG)?
+            .args(args)
+            .assert()
+            .success()
+            .stdout("");
+    }
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn one_line_per_entry() -> Result<()> {
+    Command::cargo_bin(PRG)?
+        .args(["tests/inputs/g.csv", "tests/inputs/f", "-t", "f"])
+        .assert()
+        .success()
+        .stdout(format!(
+            "tests/inputs/g.csv\n{}\n",
+            Path::new("tests/inputs/f").join("f.txt").display()
+        ));
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn path1_threads() -> Result<()> {
+    run(&["tests/inputs", "-j", "4"], "tests/expected/path1.txt")
+}
+
+// --------------------------------------------------
+#[test]
+fn path_a_b_d_threads() -> Result<()> {
+    run(
+        &["tests/inputs/a/b", "tests/inputs/d", "--threads", "2"],
+        "tests/expected/path_a_b_d.txt",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn type_f_name_a_threads() -> Result<()> {
+    run(
+        &["tests/inputs", "-t", "f", "-n", "a", "--threads", "3"],
+        "tests/expected/type_f_name_a.txt",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn gitignore_threads() -> Result<()> {
+    let dir = gen_git_tree()?;
+    assert_lines(
+        &run_in(dir.path(), &[".", "--gitignore", "-j", "2"])?,
+        &[".", "./src", "./src/main.rs", "./src/gen", "./src/gen/keep.rs"],
+    );
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn ordered_threads() -> Result<()> {
+    let file = format_file_name("tests/expected/path1.txt");
+    let contents = fs::read_to_string(file.as_ref())?;
+    let mut expected: Vec<&str> =
+        contents.split('\n').filter(|s| !s.is_empty()).collect();
+    expected.sort();
+
+    let cmd = Command::cargo_bin(PRG)?
+        .args(["tests/inputs", "-j", "4", "--ordered"])
+        .assert()
+        .success();
+    let stdout = String::from_utf8(cmd.get_output().stdout.clone())?;
+    let lines: Vec<&str> =
+        stdout.split('\n').filter(|s| !s.is_empty()).collect();
+    assert_eq!(lines, expected);
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn dies_bad_threads() -> Result<()> {
+    Command::cargo_bin(PRG)?
+        .args(["--threads", "0"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("error: invalid value '0'"));
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn type_broken() -> Result<()> {
+    run(&["tests/inputs", "-t", "broken"], "tests/expected/type_l.txt")
+}
+
+// --------------------------------------------------
+#[test]
+fn type_broken_follow() -> Result<()> {
+    run(&["tests/inputs", "-L", "-t", "broken"], "tests/expected/type_l.txt")?;
+    run(
+        &["tests/inputs", "-L", "-t", "broken", "-j", "2"],
+        "tests/expected/type_l.txt",
+    )
+}
+
+// --------------------------------------------------
+#[test]
+fn xdev() -> Result<()> {
+    run(&["tests/inputs", "--xdev"], "tests/expected/path1.txt")?;
+    run(&["tests/inputs", "--xdev", "-j", "2"], "tests/expected/path1.txt")
+}
+
+// --------------------------------------------------
+#[test]
+fn dies_follow_and_follow_args() -> Result<()> {
+    Command::cargo_bin(PRG)?
+        .args(["-L", "-H"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("cannot be used with"));
+    Ok(())
+}
+
+// --------------------------------------------------
+// real/file.txt, real/sub/, link -> real, and real/sub/up -> .. (a loop)
+#[cfg(unix)]
+fn gen_link_tree() -> Result<TempDir> {
+    use std::os::unix::fs::symlink;
+
+    let dir = tempfile::tempdir()?;
+    let root = dir.path();
+    fs::create_dir_all(root.join("real/sub"))?;
+    fs::write(root.join("real/file.txt"), "")?;
+    symlink("real", root.join("link"))?;
+    symlink("..", root.join("real/sub/up"))?;
+    Ok(dir)
+}
+
+// --------------------------------------------------
+#[test]
+#[cfg(unix)]
+fn links_not_followed() -> Result<()> {
+    let dir = gen_link_tree()?;
+    assert_lines(
+        &run_in(dir.path(), &["link", "real", "-t", "f", "l"])?,
+        &["link", "real/file.txt", "real/sub/up"],
+    );
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+#[cfg(unix)]
+fn follow_args() -> Result<()> {
+    let dir = gen_link_tree()?;
+    for threads in [&[][..], &["-j", "2"]] {
+        let args = [&["link", "-H", "-t", "f", "l"], threads].concat();
+        assert_lines(&run_in(dir.path(), &args)?, &["link/file.txt", "link/sub/up"]);
+    }
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+#[cfg(unix)]
+fn follow_reports_loop() -> Result<()> {
+    let dir = gen_link_tree()?;
+    for threads in [&[][..], &["-j", "2"]] {
+        let cmd = Command::cargo_bin(PRG)?
+            .current_dir(dir.path())
+            .args([&["real", "-L", "-t", "f"], threads].concat())
+            .assert()
+            .failure();
+        let out = cmd.get_output();
+        let stdout = String::from_utf8(out.stdout.clone())?;
+        assert_eq!(stdout, "real/file.txt\n");
+
+        let stderr = String::from_utf8(out.stderr.clone())?;
+        assert!(stderr.contains(
+            "File system loop detected; 'real/sub/up' is part of the same \
+             file system loop as 'real'"
+        ));
+    }
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+#[cfg(unix)]
+fn type_socket_fifo() -> Result<()> {
+    let dir = tempfile::tempdir()?;
+    let _sock = std::os::unix::net::UnixListener::bind(dir.path().join("sock"))?;
+    let status = std::process::Command::new("mkfifo")
+        .arg(dir.path().join("fifo"))
+        .status()?;
+    assert!(status.success());
+
+    assert_lines(&run_in(dir.path(), &[".", "-t", "s"])?, &["./sock"]);
+    assert_lines(&run_in(dir.path(), &[".", "-t", "p"])?, &["./fifo"]);
+    assert_lines(&run_in(dir.path(), &[".", "-t", "s", "p", "-j", "2"])?, &["./fifo", "./sock"]);
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+#[cfg(unix)]
+fn type_char_device() -> Result<()> {
+    Command::cargo_bin(PRG)?
+        .args(["/dev/null", "-t", "c"])
+        .assert()
+        .success()
+        .stdout("/dev/null\n");
+    Command::cargo_bin(PRG)?
+        .args(["/dev/null", "-t", "b", "f"])
+        .assert()
+        .success()
+        .stdout("");
+    Ok(())
+}
+
+// --------------------------------------------------
+// Latin-1 "café.txt" and a name with a lone 0xff byte
+#[cfg(unix)]
+fn gen_non_utf8_tree() -> Result<TempDir> {
+    use std::{ffi::OsStr, os::unix::ffi::OsStrExt};
+
+    let dir = tempfile::tempdir()?;
+    for name in [&b"caf\xe9.txt"[..], b"bad\xffname", b"plain.txt"] {
+        fs::write(dir.path().join(OsStr::from_bytes(name)), "")?;
+    }
+    Ok(dir)
+}
+
+// --------------------------------------------------
+#[test]
+#[cfg(unix)]
+fn non_utf8_names_written_as_is() -> Result<()> {
+    let dir = gen_non_utf8_tree()?;
+    assert_byte_lines(
+        &run_in(dir.path(), &[".", "-t", "f"])?,
+        &[b"./caf\xe9.txt", b"./bad\xffname", b"./plain.txt"],
+    );
+    assert_byte_lines(
+        &run_in(dir.path(), &[".", "-t", "f", "-j", "2", "--ordered"])?,
+        &[b"./caf\xe9.txt", b"./bad\xffname", b"./plain.txt"],
+    );
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+#[cfg(unix)]
+fn non_utf8_name_match() -> Result<()> {
+    let dir = gen_non_utf8_tree()?;
+    // (?-u:...) matches raw bytes instead of UTF-8 encoded characters
+    assert_byte_lines(&run_in(dir.path(), &[".", "-n", r"(?-u:\xff)"])?, &[b"./bad\xffname"]);
+    assert_byte_lines(
+        &run_in(dir.path(), &[".", "-n", r"^caf(?-u:.)[.]txt$"])?,
+        &[b"./caf\xe9.txt"],
+    );
+    assert_byte_lines(
+        &run_in(dir.path(), &[".", "-n", r"[.]txt$"])?,
+        &[b"./caf\xe9.txt", b"./plain.txt"],
+    );
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+#[cfg(unix)]
+fn non_utf8_root() -> Result<()> {
+    use std::{ffi::OsStr, os::unix::ffi::OsStrExt};
+
+    let dir = tempfile::tempdir()?;
+    let root = OsStr::from_bytes(b"caf\xe9.d");
+    fs::create_dir(dir.path().join(root))?;
+    fs::write(dir.path().join(root).join("inside.txt"), "")?;
+    for threads in [&[][..], &["-j", "2"]] {
+        Command::cargo_bin(PRG)?
+            .current_dir(dir.path())
+            .arg(root)
+            .args(["-t", "f"])
+            .args(threads)
+            .assert()
+            .success()
+            .stdout(&b"caf\xe9.d/inside.txt\n"[..]);
+    }
+    Ok(())
+}
+
+// --------------------------------------------------
+fn gen_contents_tree() -> Result<TempDir> {
+    let dir = tempfile::tempdir()?;
+    let root = dir.path();
+    fs::create_dir(root.join("world"))?;
+    fs::write(root.join("a.txt"), "hello\r\nworld\r\n")?;
+    fs::write(root.join("b.txt"), "nothing here\n")?;
+    fs::write(root.join("bin.dat"), "world\0\n")?;
+    fs::write(root.join("big.txt"), format!("{}\nworld\n", "x".repeat(2048)))?;
+    Ok(dir)
+}
+
+// --------------------------------------------------
+#[test]
+fn contains() -> Result<()> {
+    let dir = gen_contents_tree()?;
+    assert_lines(&run_in(dir.path(), &[".", "--contains", "wor+ld"])?, &["./a.txt", "./big.txt"]);
+    assert_lines(
+        &run_in(dir.path(), &[".", "--contains", "world", "-j", "2"])?,
+        &["./a.txt", "./big.txt"],
+    );
+    assert_lines(&run_in(dir.path(), &[".", "--contains", "world", "-t", "d"])?, &[]);
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn contains_max_filesize() -> Result<()> {
+    let dir = gen_contents_tree()?;
+    assert_lines(
+        &run_in(dir.path(), &[".", "--contains", "world", "--max-filesize", "1k"])?,
+        &["./a.txt"],
+    );
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn contains_print_match() -> Result<()> {
+    let dir = gen_contents_tree()?;
+    assert_lines(
+        &run_in(dir.path(), &[".", "--contains", "o", "--print-match", "-n", "[.]txt$"])?,
+        &["./a.txt:1:hello", "./b.txt:1:nothing here", "./big.txt:2:world"],
+    );
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn dies_bad_max_filesize() -> Result<()> {
+    Command::cargo_bin(PRG)?
+        .args(["--contains", "x", "--max-filesize", "10X"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("unknown size unit 'X'"));
+    Ok(())
+}
+
+// --------------------------------------------------
+// 2021-03-04 12:00:00 UTC, the same date in every time zone we test with
+const MTIME: u64 = 1_614_859_200;
+
+// docs/, docs/notes.txt (11 bytes, 0640) with a fixed mtime
+fn gen_format_tree() -> Result<TempDir> {
+    let dir = tempfile::tempdir()?;
+    fs::create_dir(dir.path().join("docs"))?;
+    let file = dir.path().join("docs/notes.txt");
+    fs::write(&file, "hello world")?;
+    let mtime = std::time::UNIX_EPOCH + std::time::Duration::from_secs(MTIME);
+    fs::File::options().write(true).open(&file)?.set_modified(mtime)?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(&file, fs::Permissions::from_mode(0o640))?;
+    }
+    Ok(dir)
+}
+
+// --------------------------------------------------
+#[test]
+fn printf() -> Result<()> {
+    let dir = gen_format_tree()?;
+    let args = ["docs", "-t", "f", "--printf", r"%p %s %TY-%Tm-%Td %T@\n"];
+    assert_stdout(
+        &run_in(dir.path(), &args)?,
+        &format!("docs/notes.txt 11 2021-03-04 {MTIME}.0000000000\n"),
+    );
+
+    assert_lines(
+        &run_in(dir.path(), &["docs", "--printf", r"[%-6f|%3d|%y|%h|%P]\t%%\n"])?,
+        &["[docs  |  0|d|.|]\t%", "[notes.txt|  1|f|docs|notes.txt]\t%"],
+    );
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn printf_depth_threads() -> Result<()> {
+    // 병렬 탐색에서도 깊이와 시작 경로는 검색 경로 기준
+    let dir = tempfile::tempdir()?;
+    fs::create_dir_all(dir.path().join("a/b/c"))?;
+    fs::write(dir.path().join("a/b/c/d.txt"), "")?;
+    for threads in ["1", "4"] {
+        let args = ["a", "--threads", threads, "--printf", r"%d %H %P\n"];
+        assert_lines(
+            &run_in(dir.path(), &args)?,
+            &["0 a ", "1 a b", "2 a b/c", "3 a b/c/d.txt"],
+        );
+    }
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+#[cfg(unix)]
+fn printf_mode() -> Result<()> {
+    let dir = gen_format_tree()?;
+    let out = run_in(dir.path(), &["docs/notes.txt", "--printf", r"%m %M %n\n"])?;
+    assert_stdout(&out, "640 -rw-r----- 1\n");
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn dies_bad_printf() -> Result<()> {
+    for (format, error) in [
+        ("%Q", "unknown directive '%Q'"),
+        ("%Tq", "unknown time field '%Tq'"),
+        ("abc%", "format ends with '%'"),
+        ("%99999999999999999999999p", "field width too large"),
+    ] {
+        Command::cargo_bin(PRG)?
+            .args(["--printf", format])
+            .assert()
+            .failure()
+            .stderr(predicate::str::contains(error));
+    }
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+#[cfg(unix)]
+fn ls() -> Result<()> {
+    let dir = gen_format_tree()?;
+    let out = stdout(&run_in(dir.path(), &["docs/notes.txt", "--ls"])?);
+    let expected = r"^ *\d+ +\d+ -rw-r----- +1 \S+ +\S+ +11 Mar  4  2021 docs/notes.txt\n$";
+    assert!(
+        regex::Regex::new(expected)?.is_match(&out),
+        "unexpected --ls output: {out:?}"
+    );
+
+    let out = stdout(&run_in(Path::new("."), &["tests/inputs/d/b.csv", "--ls"])?);
+    assert!(out.ends_with(" tests/inputs/d/b.csv -> ../a/b.csv\n"));
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn json() -> Result<()> {
+    let dir = gen_format_tree()?;
+    let out = stdout(&run_in(dir.path(), &["docs", "--json", "-j", "2", "--ordered"])?);
+    let entries = out
+        .lines()
+        .map(serde_json::from_str)
+        .collect::<Result<Vec<serde_json::Value>, _>>()?;
+    assert_eq!(entries.len(), 2);
+    assert_eq!(entries[0]["path"], "docs");
+    assert_eq!(entries[0]["type"], "directory");
+    assert_eq!(entries[1]["path"], Path::new("docs").join("notes.txt").to_str().unwrap());
+    assert_eq!(entries[1]["type"], "file");
+    assert_eq!(entries[1]["size"], 11);
+    assert_eq!(entries[1]["mtime"], MTIME as f64);
+    assert!(entries[1]["atime"].is_number());
+    assert!(entries[1]["ctime"].is_number());
+    #[cfg(unix)]
+    assert_eq!(entries[1]["mode"], "0640");
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn dies_two_output_formats() -> Result<()> {
+    Command::cargo_bin(PRG)?
+        .args(["--ls", "--json"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("cannot be used with"));
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn sort_name() -> Result<()> {
+    assert_stdout(
+        &run_in(Path::new("."), &["tests/inputs", "--sort", "name"])?,
+        &expected("tests/expected/path1.txt")?,
+    );
+    assert_stdout(
+        &run_in(Path::new("."), &["tests/inputs", "--sort", "path"])?,
+        &expected("tests/expected/path1.txt")?,
+    );
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn sort_name_reverse() -> Result<()> {
+    assert_stdout(
+        &run_in(Path::new("."), &["tests/inputs", "--sort", "name", "--reverse"])?,
+        &expected("tests/expected/sort_name_reverse.txt")?,
+    );
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn sort_global() -> Result<()> {
+    assert_stdout(
+        &run_in(
+            Path::new("."),
+            &["tests/inputs", "-t", "f", "--sort", "name", "--sort-scope", "global"],
+        )?,
+        &expected("tests/expected/sort_global_name_type_f.txt")?,
+    );
+    // parallel results are always sorted as a whole
+    assert_stdout(
+        &run_in(Path::new("."), &["tests/inputs", "-t", "f", "--sort", "name", "-j", "4"])?,
+        &expected("tests/expected/sort_global_name_type_f.txt")?,
+    );
+    Ok(())
+}
+
+// --------------------------------------------------
+// big (oldest), mid, small (newest)
+fn gen_sized_tree() -> Result<TempDir> {
+    let dir = tempfile::tempdir()?;
+    for (i, (name, size)) in [("big", 30), ("mid", 20), ("small", 10)].iter().enumerate() {
+        let file = dir.path().join(name);
+        fs::write(&file, "x".repeat(*size))?;
+        let mtime = std::time::UNIX_EPOCH
+            + std::time::Duration::from_secs(MTIME + 60 * i as u64);
+        fs::File::options().write(true).open(&file)?.set_modified(mtime)?;
+    }
+    Ok(dir)
+}
+
+// --------------------------------------------------
+#[test]
+fn sort_size_mtime() -> Result<()> {
+    let dir = gen_sized_tree()?;
+    let dir = dir.path();
+    let ascending = "./small\n./mid\n./big\n";
+    let descending = "./big\n./mid\n./small\n";
+    assert_stdout(&run_in(dir, &[".", "-t", "f", "--sort", "size"])?, ascending);
+    assert_stdout(&run_in(dir, &[".", "-t", "f", "--sort", "size", "--reverse"])?, descending);
+    assert_stdout(&run_in(dir, &[".", "-t", "f", "--sort", "mtime"])?, descending);
+    assert_stdout(
+        &run_in(dir, &[".", "-t", "f", "--sort", "mtime", "--reverse", "-j", "2"])?,
+        ascending,
+    );
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn dies_reverse_without_sort() -> Result<()> {
+    Command::cargo_bin(PRG)?
+        .arg("--reverse")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("--sort <KEY>"));
+    Ok(())
+}
+
+// --------------------------------------------------
+// a.jpg = b.jpg = d.txt, c.jpg differs but has the same size,
+// big1 = big3 while big2 only shares their first 4 KiB, and
+// two empty files that are never reported
+fn gen_dupes_tree() -> Result<TempDir> {
+    let dir = tempfile::tempdir()?;
+    let root = dir.path();
+    fs::create_dir(root.join("sub"))?;
+    let head = "x".repeat(5000);
+    let files = [
+        ("a.jpg", "same content".to_string()),
+        ("sub/b.jpg", "same content".to_string()),
+        ("c.jpg", "same contenX".to_string()),
+        ("d.txt", "same content".to_string()),
+        ("big1", format!("{head}1")),
+        ("big2", format!("{head}2")),
+        ("sub/big3", format!("{head}1")),
+        ("e.jpg", String::new()),
+        ("f.jpg", String::new()),
+    ];
+    for (name, contents) in files {
+        fs::write(root.join(name), contents)?;
+    }
+    Ok(dir)
+}
+
+// --------------------------------------------------
+#[test]
+fn duplicates() -> Result<()> {
+    let dir = gen_dupes_tree()?;
+    let sep = std::path::MAIN_SEPARATOR;
+    assert_stdout(
+        &run_in(dir.path(), &[".", "--duplicates"])?,
+        &format!("./a.jpg\n./d.txt\n./sub{sep}b.jpg\n\n./big1\n./sub{sep}big3\n"),
+    );
+    assert_stdout(
+        &run_in(dir.path(), &[".", "--duplicates", "-t", "f", "-n", "[.]jpg$", "-j", "2"])?,
+        &format!("./a.jpg\n./sub{sep}b.jpg\n"),
+    );
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn duplicates_overlapping_paths() -> Result<()> {
+    // a file reached through two search paths is not a duplicate of itself
+    let dir = gen_dupes_tree()?;
+    let sep = std::path::MAIN_SEPARATOR;
+    assert_stdout(
+        &run_in(dir.path(), &[".", ".", "--duplicates", "-n", "^big"])?,
+        &format!("./big1\n./sub{sep}big3\n"),
+    );
+
+    Command::cargo_bin(PRG)?
+        .current_dir(dir.path())
+        .args([".", ".", "--duplicates", "--delete-dupes", "-n", "^big"])
+        .write_stdin("y\n")
+        .assert()
+        .success()
+        .stderr(predicate::str::contains("1 files deleted"));
+    assert!(dir.path().join("big1").exists());
+    assert!(!dir.path().join("sub/big3").exists());
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+#[cfg(unix)]
+fn duplicates_hardlink() -> Result<()> {
+    use std::os::unix::fs::MetadataExt;
+
+    let dir = gen_dupes_tree()?;
+    Command::cargo_bin(PRG)?
+        .current_dir(dir.path())
+        .args([".", "--duplicates", "--hardlink", "-n", "[.](jpg|txt)$"])
+        .write_stdin("y\n")
+        .assert()
+        .success()
+        .stderr(predicate::str::contains("2 files replaced with hard links"));
+
+    let ino = |name: &str| fs::metadata(dir.path().join(name)).map(|m| m.ino());
+    assert_eq!(ino("a.jpg")?, ino("sub/b.jpg")?);
+    assert_eq!(ino("a.jpg")?, ino("d.txt")?);
+    assert_ne!(ino("big1")?, ino("sub/big3")?);
+
+    // already linked files are not reported again
+    assert_stdout(&run_in(dir.path(), &[".", "--duplicates", "-n", "[.]jpg$"])?, "");
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn duplicates_delete() -> Result<()> {
+    let dir = gen_dupes_tree()?;
+    let args = [".", "--duplicates", "--delete-dupes", "-n", "^big"];
+
+    Command::cargo_bin(PRG)?
+        .current_dir(dir.path())
+        .args(args)
+        .write_stdin("n\n")
+        .assert()
+        .success()
+        .stderr(predicate::str::contains("Delete 1 duplicate files? [y/N]"));
+    assert!(dir.path().join("sub/big3").exists());
+
+    Command::cargo_bin(PRG)?
+        .current_dir(dir.path())
+        .args(args)
+        .write_stdin("y\n")
+        .assert()
+        .success()
+        .stderr(predicate::str::contains("1 files deleted"));
+    assert!(dir.path().join("big1").exists());
+    assert!(dir.path().join("big2").exists());
+    assert!(!dir.path().join("sub/big3").exists());
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn stats() -> Result<()> {
+    let dir = gen_sized_tree()?;
+    fs::create_dir_all(dir.path().join("sub/deep"))?;
+    fs::write(dir.path().join("sub/deep/main.rs"), "fn main() {}\n")?;
+    fs::write(dir.path().join("sub/lib.rs"), "")?;
+    fs::write(dir.path().join("sub/notes.txt"), "todo\n")?;
+
+    let expected = [
+        "entries: 9",
+        "  directory: 3",
+        "  file: 6",
+        "total size: 78",
+        "largest file: 30 ./big",
+        "deepest path: 3 ./sub/deep/main.rs",
+        "extensions:",
+        "  (none): 3 files, 60 bytes",
+        "  .rs: 2 files, 13 bytes",
+        "  .txt: 1 files, 5 bytes",
+    ]
+    .join("\n");
+    assert_stats(&run_in(dir.path(), &[".", "--stats"])?, &expected);
+    assert_stats(&run_in(dir.path(), &[".", "--stats", "-j", "4"])?, &expected);
+
+    let expected = [
+        "entries: 2",
+        "  file: 2",
+        "total size: 13",
+        "largest file: 13 ./sub/deep/main.rs",
+        "deepest path: 3 ./sub/deep/main.rs",
+        "extensions:",
+        "  .rs: 2 files, 13 bytes",
+    ]
+    .join("\n");
+    assert_stats(&run_in(dir.path(), &[".", "--stats", "-n", "rs$"])?, &expected);
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn dies_stats_with_output_format() -> Result<()> {
+    Command::cargo_bin(PRG)?
+        .args(["tests/inputs", "--stats", "--json"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("cannot be used with"));
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+#[cfg(not(windows))]
+fn path_relative_to_root() -> Result<()> {
+    let inputs = Path::new("tests/inputs");
+    assert_lines(
+        &run_in(inputs, &[".", "--path", "^a/b"])?,
+        &["./a/b", "./a/b/b.csv", "./a/b/c", "./a/b/c/c.mp3"],
+    );
+    // 검색 경로 자체는 매칭 대상에 포함되지 않음
+    assert_lines(&run_in(Path::new("tests"), &["inputs", "--path", "^inputs"])?, &[]);
+    assert_lines(&run_in(Path::new("tests"), &["inputs", "--path", "^d/e$"])?, &["inputs/d/e"]);
+    assert_lines(
+        &run_in(inputs, &[".", "--path", "^a/b", "-j", "2", "-n", "mp3$"])?,
+        &["./a/b/c/c.mp3"],
+    );
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+#[cfg(not(windows))]
+fn ipath() -> Result<()> {
+    let inputs = Path::new("tests/inputs");
+    assert_lines(&run_in(inputs, &[".", "--ipath", "A/B/C$"])?, &["./a/b/c"]);
+    assert_lines(&run_in(inputs, &[".", "--path", "A/B/C$"])?, &[]);
+    // 여러 패턴은 OR
+    assert_lines(
+        &run_in(inputs, &[".", "--path", "^f/", "--ipath", "^D/E/"])?,
+        &["./d/e/e.mp3", "./f/f.txt"],
+    );
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+#[cfg(not(windows))]
+fn absolute_path() -> Result<()> {
+    let inputs = Path::new("tests/inputs");
+    assert_lines(&run_in(inputs, &["a", "--path", "^/.*/inputs/a/b$"])?, &[]);
+    assert_lines(
+        &run_in(inputs, &["a", "--absolute-path", "--path", "^/.*/inputs/a/b$"])?,
+        &["a/b"],
+    );
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+#[cfg(not(windows))]
+fn watch() -> Result<()> {
+    use std::io::{BufRead, BufReader};
+    use std::process::Stdio;
+    use std::sync::mpsc;
+    use std::time::Duration;
+
+    let dir = tempfile::tempdir()?;
+    fs::write(dir.path().join("old.txt"), "")?;
+    let mut child = std::process::Command::new(assert_cmd::cargo::cargo_bin(PRG))
+        .current_dir(dir.path())
+        .args([".", "--watch", "-t", "f", "-n", "txt$"])
+        .stdout(Stdio::piped())
+        .spawn()?;
+
+    // 출력은 끝나지 않으므로 별도 스레드에서 한 줄씩 읽음
+    let stdout = child.stdout.take().unwrap();
+    let (tx, rx) = mpsc::channel();
+    std::thread::spawn(move || {
+        for line in BufReader::new(stdout).lines().map_while(|l| l.ok()) {
+            let _ = tx.send(line);
+        }
+    });
+    let next = || rx.recv_timeout(Duration::from_secs(10));
+
+    assert_eq!(next()?, "found ./old.txt");
+    // 필터에 맞지 않는 항목은 보고하지 않음
+    fs::create_dir(dir.path().join("sub.txt"))?;
+    fs::write(dir.path().join("new.rs"), "")?;
+    fs::write(dir.path().join("sub.txt/new.txt"), "")?;
+    assert_eq!(next()?, "created ./sub.txt/new.txt");
+    fs::remove_file(dir.path().join("old.txt"))?;
+    // 새 디렉토리의 파일은 디렉토리 탐색과 자신의 이벤트 양쪽에서 오지만 한 번만 보고
+    assert_eq!(next()?, "removed ./old.txt");
+
+    child.kill()?;
+    child.wait()?;
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn dies_watch_with_stats() -> Result<()> {
+    Command::cargo_bin(PRG)?
+        .args(["tests/inputs", "--watch", "--stats"])
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("cannot be used with"));
+    Ok(())
+}
+
+// --------------------------------------------------
+// out/ 아래에 같은 내용의 tar, tar.gz, zip 파일을 만듦
+fn gen_archive_tree() -> Result<TempDir> {
+    use std::io::Write;
+
+    let dir = tempfile::tempdir()?;
+    let out = dir.path().join("out");
+    fs::create_dir(&out)?;
+    fs::write(out.join("notes.txt"), "")?;
+
+    fn add_tar<W: Write>(builder: &mut tar::Builder<W>) -> Result<()> {
+        let mut header = tar::Header::new_gnu();
+        header.set_entry_type(tar::EntryType::Directory);
+        header.set_mode(0o755);
+        header.set_size(0);
+        builder.append_data(&mut header.clone(), "./src/", std::io::empty())?;
+        header.set_entry_type(tar::EntryType::Regular);
+        header.set_mode(0o644);
+        header.set_size(4);
+        builder.append_data(&mut header.clone(), "./src/main.rs", &b"fn \n"[..])?;
+        builder.append_data(&mut header, "./README.md", &b"# x\n"[..])?;
+        Ok(())
+    }
+    let mut tar = tar::Builder::new(fs::File::create(out.join("plain.tar"))?);
+    add_tar(&mut tar)?;
+    tar.finish()?;
+
+    let gz = flate2::write::GzEncoder::new(
+        fs::File::create(out.join("bundle.tar.gz"))?,
+        flate2::Compression::default(),
+    );
+    let mut tgz = tar::Builder::new(gz);
+    add_tar(&mut tgz)?;
+    tgz.into_inner()?.finish()?;
+
+    let mut zip = zip::ZipWriter::new(fs::File::create(out.join("app.ZIP"))?);
+    let options = zip::write::SimpleFileOptions::default();
+    zip.add_directory("src/", options)?;
+    zip.start_file("src/main.rs", options)?;
+    zip.write_all(b"fn \n")?;
+    zip.start_file("README.md", options)?;
+    zip.write_all(b"# x\n")?;
+    zip.finish()?;
+    Ok(dir)
+}
+
+// --------------------------------------------------
+#[test]
+fn search_archives() -> Result<()> {
+    let dir = gen_archive_tree()?;
+    let mut expected = vec!["out".to_string(), "out/notes.txt".to_string()];
+    for archive in ["out/app.ZIP", "out/bundle.tar.gz", "out/plain.tar"] {
+        expected.push(archive.to_string());
+        for member in ["!/src", "!/src/main.rs", "!/README.md"] {
+            expected.push(format!("{archive}{member}"));
+        }
+    }
+    let expected: Vec<&str> = expected.iter().map(String::as_str).collect();
+    assert_lines(&run_in(dir.path(), &["out", "--search-archives"])?, &expected);
+    assert_lines(&run_in(dir.path(), &["out", "--search-archives", "-j", "2"])?, &expected);
+    // 옵션 없이는 압축 파일 안을 보지 않음
+    assert_lines(&run_in(dir.path(), &["out", "-n", "rs$"])?, &[]);
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn search_archives_sort() -> Result<()> {
+    // 압축 파일 안에는 src/main.rs, README.md 순서로 들어 있음
+    let dir = gen_archive_tree()?;
+    let args = |extra: &[&'static str]| [&["out/plain.tar", "--search-archives", "-t", "f"], extra].concat();
+    let tar = "out/plain.tar\n";
+    let members = "out/plain.tar!/README.md\nout/plain.tar!/src/main.rs\n";
+    let reversed = "out/plain.tar!/src/main.rs\nout/plain.tar!/README.md\n";
+    assert_stdout(&run_in(dir.path(), &args(&["--sort", "name"]))?, &format!("{tar}{members}"));
+    assert_stdout(
+        &run_in(dir.path(), &args(&["--sort", "name", "--reverse"]))?,
+        &format!("{tar}{reversed}"),
+    );
+    assert_stdout(
+        &run_in(dir.path(), &args(&["--sort", "name", "--sort-scope", "global"]))?,
+        &format!("{members}{tar}"),
+    );
+    assert_stdout(
+        &run_in(dir.path(), &args(&["--sort", "name", "--sort-scope", "global", "--reverse"]))?,
+        &format!("{tar}{reversed}"),
+    );
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn search_archives_filters() -> Result<()> {
+    let dir = gen_archive_tree()?;
+    assert_lines(
+        &run_in(dir.path(), &["out", "--search-archives", "-t", "f", "-n", "rs$"])?,
+        &["out/app.ZIP!/src/main.rs", "out/bundle.tar.gz!/src/main.rs", "out/plain.tar!/src/main.rs"],
+    );
+    assert_lines(
+        &run_in(dir.path(), &["out", "--search-archives", "-t", "d"])?,
+        &["out", "out/app.ZIP!/src", "out/bundle.tar.gz!/src", "out/plain.tar!/src"],
+    );
+    assert_lines(
+        &run_in(dir.path(), &[".", "--search-archives", "--path", "tar!/README"])?,
+        &["./out/plain.tar!/README.md"],
+    );
+    assert_lines(
+        &run_in(
+            dir.path(),
+            &["out", "--search-archives", "--absolute-path", "--path", "/out/[^/]*tar!/READ"],
+        )?,
+        &["out/plain.tar!/README.md"],
+    );
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn search_archives_bad_archive() -> Result<()> {
+    let dir = tempfile::tempdir()?;
+    fs::write(dir.path().join("broken.zip"), "not a zip")?;
+    Command::cargo_bin(PRG)?
+        .current_dir(dir.path())
+        .args([".", "--search-archives"])
+        .assert()
+        .failure()
+        .stdout(".\n./broken.zip\n")
+        .stderr(predicate::str::starts_with("findr: ./broken.zip: "));
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn search_archives_damaged_tar() -> Result<()> {
+    // src/ 와 src/main.rs 다음에 망가진 헤더가 있는 tar
+    let tree = gen_archive_tree()?;
+    let tar = fs::read(tree.path().join("out/plain.tar"))?;
+    let dir = tempfile::tempdir()?;
+    fs::write(dir.path().join("damaged.tar"), [&tar[..1536], &[b'x'; 512]].concat())?;
+    Command::cargo_bin(PRG)?
+        .current_dir(dir.path())
+        .args([".", "--search-archives"])
+        .assert()
+        .failure()
+        .stdout(".\n./damaged.tar\n./damaged.tar!/src\n./damaged.tar!/src/main.rs\n")
+        .stderr(predicate::str::starts_with("findr: ./damaged.tar: "));
+    Ok(())
+}
+
 // --------------------------------------------------
 #[test]
 #[cfg(not(windows))]
@@ -294,14 +1359,17 @@ fn unreadable_dir() -> Result<()> {
         .args(["000", dirname])
         .status()
         .expect("failed");
+    // root 는 권한과 상관없이 읽을 수 있으므로 확인할 수 없음
+    if fs::read_dir(dirname).is_ok() {
+        fs::remove_dir(dirname)?;
+        eprintln!("skipping unreadable_dir: directory is still readable");
+        return Ok(());
+    }
 
-    let cmd = Command::cargo_bin(PRG)?
-        .arg("tests/inputs")
-        .assert()
-        .success();
+    let out = Command::cargo_bin(PRG)?.arg("tests/inputs").output()?;
     fs::remove_dir(dirname)?;
+    assert!(!out.status.success());
 
-    let out = cmd.get_output();
     let stdout = String::from_utf8(out.stdout.clone())?;
     let lines: Vec<&str> =
         stdout.split('\n').filter(|s| !s.is_empty()).collect();
@@ -312,3 +1380,118 @@ fn unreadable_dir() -> Result<()> {
     assert!(stderr.contains("cant-touch-this: Permission denied"));
     Ok(())
 }
+
+// --------------------------------------------------
+// Builds a small git work tree in a temp dir:
+// .gitignore, .ignore, .git/info/exclude, a hidden file and
+// a nested .gitignore that re-includes a file.
+fn gen_git_tree() -> Result<TempDir> {
+    let dir = tempfile::tempdir()?;
+    let root = dir.path();
+    for d in [".git/info", "src/gen", "target/debug", "docs"] {
+        fs::create_dir_all(root.join(d))?;
+    }
+    let files = [
+        (".gitignore", "target/\n*.log\n"),
+        (".git/info/exclude", "*.bak\n"),
+        (".ignore", "docs/\n"),
+        (".env", "SECRET=1\n"),
+        ("src/main.rs", ""),
+        ("src/main.rs.bak", ""),
+        ("src/gen/.gitignore", "*\n!keep.rs\n"),
+        ("src/gen/out.rs", ""),
+        ("src/gen/keep.rs", ""),
+        ("target/debug/findr", ""),
+        ("build.log", ""),
+        ("docs/index.md", ""),
+    ];
+    for (name, contents) in files {
+        fs::write(root.join(name), contents)?;
+    }
+    Ok(dir)
+}
+
+// --------------------------------------------------
+#[test]
+fn gitignore() -> Result<()> {
+    let dir = gen_git_tree()?;
+    assert_lines(
+        &run_in(dir.path(), &[".", "--gitignore"])?,
+        &[".", "./src", "./src/main.rs", "./src/gen", "./src/gen/keep.rs"],
+    );
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn gitignore_hidden() -> Result<()> {
+    let dir = gen_git_tree()?;
+    assert_lines(
+        &run_in(dir.path(), &[".", "--gitignore", "--hidden", "-t", "f"])?,
+        &[
+            "./.env",
+            "./.gitignore",
+            "./.ignore",
+            "./src/main.rs",
+            "./src/gen/keep.rs",
+        ],
+    );
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn gitignore_explicit_root() -> Result<()> {
+    let dir = gen_git_tree()?;
+    assert_lines(
+        &run_in(dir.path(), &["target", "--gitignore"])?,
+        &["target", "target/debug", "target/debug/findr"],
+    );
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn no_gitignore() -> Result<()> {
+    let dir = gen_git_tree()?;
+    assert_lines(
+        &run_in(dir.path(), &[".", "-n", "[.]log$", "out[.]rs$"])?,
+        &["./build.log", "./src/gen/out.rs"],
+    );
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn gitignore_bad_pattern() -> Result<()> {
+    let dir = tempfile::tempdir()?;
+    fs::write(dir.path().join(".ignore"), "[z-a]\n*.log\n")?;
+    fs::write(dir.path().join("a.log"), "")?;
+    fs::write(dir.path().join("a.rs"), "")?;
+    Command::cargo_bin(PRG)?
+        .current_dir(dir.path())
+        .args([".", "--gitignore", "--sort", "path"])
+        .assert()
+        .failure()
+        .stdout(".\n./a.rs\n")
+        .stderr(predicate::str::is_match("^findr: .*[.]ignore: line 1: [^\n]*\n$")?);
+    // 항목을 읽지 못한 것은 아니므로 요약에는 세지 않음
+    Command::cargo_bin(PRG)?
+        .current_dir(dir.path())
+        .args([".", "--gitignore", "--quiet-errors"])
+        .assert()
+        .failure()
+        .stderr("");
+    Ok(())
+}
+
+// --------------------------------------------------
+#[test]
+fn dies_hidden_without_gitignore() -> Result<()> {
+    Command::cargo_bin(PRG)?
+        .arg("--hidden")
+        .assert()
+        .failure()
+        .stderr(predicate::str::contains("--gitignore"));
+    Ok(())
+}
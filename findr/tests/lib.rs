@@ -0,0 +1,135 @@
+use findr::matcher::{And, Compare, EntryType, Matcher, Name, Not, Or, Path, Size, Time, TimeKind, Type};
+use pretty_assertions::assert_eq;
+use regex::bytes::Regex;
+use std::cmp::Ordering;
+use std::fs;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+// --------------------------------------------------
+fn find(matcher: impl Matcher) -> Vec<String> {
+    let mut paths: Vec<String> = findr::find(["tests/inputs"], matcher)
+        .map(|e| e.path().to_string_lossy().replace('\\', "/"))
+        .collect();
+    paths.sort();
+    paths
+}
+
+// --------------------------------------------------
+fn name(re: &str) -> Box<dyn Matcher> {
+    Box::new(Name(Regex::new(re).unwrap()))
+}
+
+// --------------------------------------------------
+#[test]
+fn and_or() {
+    let matcher = And(vec![
+        Box::new(Type(EntryType::File)),
+        Box::new(Or(vec![name("[.]csv$"), name("[.]mp3$")])),
+    ]);
+    assert_eq!(
+        find(matcher),
+        [
+            "tests/inputs/a/b/b.csv",
+            "tests/inputs/a/b/c/c.mp3",
+            "tests/inputs/d/e/e.mp3",
+            "tests/inputs/g.csv",
+        ]
+    );
+}
+
+// --------------------------------------------------
+#[test]
+fn not() {
+    let matcher = And(vec![Box::new(Type(EntryType::Dir)), Box::new(Not(name("^[a-d]$")))]);
+    assert_eq!(find(matcher), ["tests/inputs", "tests/inputs/d/e", "tests/inputs/f"]);
+}
+
+// --------------------------------------------------
+#[test]
+fn path_and_size() {
+    let matcher = And(vec![
+        Box::new(Path::new(Regex::new("^a/").unwrap())),
+        Box::new(Size(Compare {
+            ordering: Ordering::Greater,
+            value: 0,
+        })),
+        Box::new(Type(EntryType::File)),
+    ]);
+    assert_eq!(find(matcher), ["tests/inputs/a/a.txt", "tests/inputs/a/b/b.csv", "tests/inputs/a/b/c/c.mp3"]);
+}
+
+// --------------------------------------------------
+#[test]
+fn absolute_path() {
+    let matcher = Path::absolute(Regex::new("/tests/inputs/d/e$").unwrap()).unwrap();
+    assert_eq!(find(matcher), ["tests/inputs/d/e"]);
+}
+
+// --------------------------------------------------
+#[test]
+fn depth() {
+    let depths: Vec<usize> = findr::find(["tests/inputs/a/b"], Type(EntryType::File))
+        .map(|e| e.depth())
+        .collect();
+    assert_eq!(depths.iter().max(), Some(&2));
+}
+
+// --------------------------------------------------
+#[test]
+fn time() {
+    const DAY: Duration = Duration::from_secs(24 * 60 * 60);
+    let now = SystemTime::now();
+    let days = |ordering, value| Compare { ordering, value };
+
+    // 열흘 전에 수정된 파일과 그 파일만 든 tar
+    let dir = tempfile::tempdir().unwrap();
+    let old = dir.path().join("old.txt");
+    fs::write(&old, "old\n").unwrap();
+    fs::File::options().write(true).open(&old).unwrap().set_modified(now - 10 * DAY).unwrap();
+    fs::write(dir.path().join("new.txt"), "new\n").unwrap();
+    let mut tar = tar::Builder::new(fs::File::create(dir.path().join("old.tar")).unwrap());
+    let mut header = tar::Header::new_gnu();
+    header.set_size(4);
+    header.set_mode(0o644);
+    header.set_mtime((now - 10 * DAY).duration_since(UNIX_EPOCH).unwrap().as_secs());
+    tar.append_data(&mut header, "old.txt", &b"old\n"[..]).unwrap();
+    tar.finish().unwrap();
+
+    let names = |matcher: Time| -> Vec<String> {
+        let files = And(vec![Box::new(Type(EntryType::File)), Box::new(matcher)]);
+        let mut names: Vec<String> = findr::find([dir.path()], files)
+            .map(|e| e.file_name().to_string_lossy().into_owned())
+            .collect();
+        names.sort();
+        names
+    };
+    let modified = |days| Time { kind: TimeKind::Modify, days, now };
+    assert_eq!(names(modified(days(Ordering::Greater, 5))), ["old.txt"]);
+    assert_eq!(names(modified(days(Ordering::Equal, 0))), ["new.txt", "old.tar"]);
+
+    // 압축 파일에는 수정 시각만 있음
+    let archive = findr::find([dir.path()], Name(Regex::new("tar$").unwrap())).next().unwrap();
+    let mut members = vec![];
+    findr::archive::members(&archive, &mut members).unwrap();
+    assert!(modified(days(Ordering::Equal, 10)).is_member_match(&members[0]));
+    assert!(!modified(days(Ordering::Less, 1)).is_member_match(&members[0]));
+    let accessed = Time { kind: TimeKind::Access, days: days(Ordering::Greater, 5), now };
+    assert!(!accessed.is_member_match(&members[0]));
+}
+
+// --------------------------------------------------
+#[test]
+fn empty_combinators() {
+    assert_eq!(find(And(vec![])).len(), 16);
+    assert!(find(Or(vec![])).is_empty());
+}
+
+// --------------------------------------------------
+#[test]
+fn compare_parse() {
+    let parse = |s: &str| Compare::parse(s, |n| n.parse::<u64>().map_err(|e| e.to_string()));
+    assert_eq!(parse("+3"), Ok(Compare { ordering: Ordering::Greater, value: 3 }));
+    assert_eq!(parse("-3"), Ok(Compare { ordering: Ordering::Less, value: 3 }));
+    assert_eq!(parse("3"), Ok(Compare { ordering: Ordering::Equal, value: 3 }));
+    assert!(parse("+x").is_err());
+}